@@ -0,0 +1,138 @@
+//! Decoding large untagged payloads.
+//!
+//! `clone_per_attempt` reproduces the original strategy of cloning the buffered value for every
+//! branch attempt, as a baseline for the borrowed decode the crate now uses.
+//!
+//! The `from_str` groups compare parsing text into `T` directly, buffering it through a
+//! `serde_json::Value` first, and decoding a `JsonResult`, which records a token tape instead.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use json_result::r#enum;
+use json_result::r#struct::JsonResult;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Item {
+    id: u64,
+    name: String,
+    tags: Vec<String>,
+    price: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Page {
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApiError {
+    code: u32,
+    message: String,
+}
+
+fn page(len: usize) -> Value {
+    let items: Vec<Value> = (0..len)
+        .map(|i| {
+            json!({
+                "id": i,
+                "name": format!("item number {}", i),
+                "tags": ["alpha", "beta", "gamma"],
+                "price": i as f64 * 1.25,
+            })
+        })
+        .collect();
+    json!({ "items": items })
+}
+
+fn clone_per_attempt<T, E>(value: Value) -> Result<T, E>
+where
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    if let Ok(ok) = serde_json::from_value(value.clone()) {
+        return Ok(ok);
+    }
+    if let Ok(err) = serde_json::from_value(value.clone()) {
+        return Err(err);
+    }
+    panic!("payload matches neither branch")
+}
+
+fn bench_decode(c: &mut Criterion) {
+    for len in [1_000, 100_000] {
+        let ok = page(len);
+        let bytes = serde_json::to_vec(&ok).unwrap().len() as u64;
+
+        let mut group = c.benchmark_group(format!("ok_{}_items", len));
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(bytes));
+        group.bench_with_input(BenchmarkId::new("clone_per_attempt", len), &ok, |b, v| {
+            b.iter(|| clone_per_attempt::<Page, ApiError>(v.clone()).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("struct", len), &ok, |b, v| {
+            b.iter(|| serde_json::from_value::<JsonResult<Page, ApiError>>(v.clone()).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("enum", len), &ok, |b, v| {
+            b.iter(|| r#enum::JsonResult::<Page, ApiError>::try_from(v.clone()).unwrap())
+        });
+        group.finish();
+
+        // An error payload as large as the page, so every cloned attempt copies all of it.
+        let mut err = ok.clone();
+        err["code"] = json!(500);
+        err["message"] = json!("upstream failed");
+        err["items"] = json!("truncated");
+        err["trace"] = ok["items"].clone();
+
+        let mut group = c.benchmark_group(format!("err_{}_items", len));
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(bytes));
+        group.bench_with_input(BenchmarkId::new("clone_per_attempt", len), &err, |b, v| {
+            b.iter(|| clone_per_attempt::<Page, ApiError>(v.clone()).unwrap_err())
+        });
+        group.bench_with_input(BenchmarkId::new("struct", len), &err, |b, v| {
+            b.iter(|| serde_json::from_value::<JsonResult<Page, ApiError>>(v.clone()).unwrap())
+        });
+        group.finish();
+    }
+}
+
+fn via_value<T, E>(s: &str) -> Result<T, E>
+where
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    let value: Value = serde_json::from_str(s).unwrap();
+    if let Ok(ok) = T::deserialize(&value) {
+        return Ok(ok);
+    }
+    if let Ok(err) = E::deserialize(&value) {
+        return Err(err);
+    }
+    panic!("payload matches neither branch")
+}
+
+fn bench_from_str(c: &mut Criterion) {
+    for len in [1_000, 100_000] {
+        let text = page(len).to_string();
+
+        let mut group = c.benchmark_group(format!("from_str_{}_items", len));
+        group.sample_size(10);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::new("direct", len), &text, |b, s| {
+            b.iter(|| serde_json::from_str::<Page>(s).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("via_value", len), &text, |b, s| {
+            b.iter(|| via_value::<Page, ApiError>(s).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("struct", len), &text, |b, s| {
+            b.iter(|| serde_json::from_str::<JsonResult<Page, ApiError>>(s).unwrap())
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_decode, bench_from_str);
+criterion_main!(benches);
@@ -0,0 +1,547 @@
+//! Element-by-element decoding of a top-level JSON array of `JsonResult` records.
+//!
+//! Bulk endpoints often answer with one large array whose elements are each a success or an
+//! error. [`ArrayStream`] reads such an array from any [`io::Read`] one element at a time, so
+//! memory stays bounded by the largest element rather than the whole response, and an element
+//! that fits neither type is reported on its own instead of failing the rest. An element larger
+//! than [`ArrayStream::with_max_element_size`] allows ends the stream, so a malformed element
+//! such as an unterminated string cannot buffer the rest of the input.
+//!
+//! ```rust
+//! use json_result::array::ArrayStream;
+//!
+//! let body = r#"[{"id": 1}, {"error": "quota"}, {"id": "x"}, {"id": 2}]"#;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Item {
+//!     id: u32,
+//! }
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Failure {
+//!     error: String,
+//! }
+//!
+//! let mut ok = 0;
+//! let mut failed = Vec::new();
+//! for element in ArrayStream::<_, Item, Failure>::new(body.as_bytes()) {
+//!     match element {
+//!         Ok(jr) if jr.is_ok() => ok += 1,
+//!         Ok(_) => {}
+//!         Err(e) => failed.push(e.index()),
+//!     }
+//! }
+//!
+//! assert_eq!(ok, 2);
+//! assert_eq!(failed, vec![2]);
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::marker::PhantomData;
+
+use serde::de::{DeserializeOwned, Error as _};
+
+use crate::error::{DecodeError, ElementError};
+use crate::repr::{PreferOk, Untagged};
+use crate::r#struct::JsonResult;
+
+/// Where the stream is in the outer array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the opening `[`.
+    Start,
+    /// After `[`, where `]` closes an empty array.
+    First,
+    /// After a `,`, where an element must follow.
+    Next,
+    /// After the closing `]`, where only whitespace may follow.
+    Closed,
+    /// The input ended inside the array after a complete element.
+    Truncated,
+    /// Nothing more to yield.
+    Done,
+}
+
+/// Default for [`ArrayStream::with_max_element_size`]: 16 MiB.
+pub const DEFAULT_MAX_ELEMENT_SIZE: usize = 16 * 1024 * 1024;
+
+/// What ended an element.
+enum End {
+    Comma,
+    Close,
+    Eof,
+    /// The element grew past the size limit before it ended.
+    TooLarge,
+}
+
+/// ArrayStream<R, T, E>
+///
+/// Reads a top-level JSON array from `R` and decodes each element as `T`, falling back to `E`.
+///
+/// Elements are delimited by scanning the raw bytes, so a malformed or mismatched element
+/// yields an [`ElementError`] with its index and the next call moves on to the following
+/// element. Errors about the array itself (input that is not an array, an I/O failure, the
+/// input ending before `]`, a missing element such as a trailing comma, data after the array,
+/// or an element over the size limit) are yielded once and end the stream.
+///
+/// Line and column numbers inside an element's error are relative to the element.
+pub struct ArrayStream<R, T, E> {
+    reader: BufReader<R>,
+    buf: Vec<u8>,
+    index: usize,
+    state: State,
+    max_element_size: usize,
+    marker: PhantomData<fn() -> (T, E)>,
+}
+
+impl<R, T, E> ArrayStream<R, T, E>
+where
+    R: Read,
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    /// Creates a stream over `reader`.
+    pub fn new(reader: R) -> Self {
+        ArrayStream {
+            reader: BufReader::new(reader),
+            buf: Vec::new(),
+            index: 0,
+            state: State::Start,
+            max_element_size: DEFAULT_MAX_ELEMENT_SIZE,
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets the largest element, in bytes, the stream buffers before giving up. Defaults to
+    /// [`DEFAULT_MAX_ELEMENT_SIZE`].
+    ///
+    /// A larger element yields an [`ElementError`] with its index and ends the stream, since
+    /// there is no telling where it would have ended.
+    pub fn with_max_element_size(mut self, bytes: usize) -> Self {
+        self.max_element_size = bytes;
+        self
+    }
+
+    /// Number of elements read so far.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Skips whitespace and returns the next byte without consuming it.
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            let chunk = fill_buf(&mut self.reader)?;
+            if chunk.is_empty() {
+                return Ok(None);
+            }
+            match chunk.iter().position(|b| !is_whitespace(*b)) {
+                Some(i) => {
+                    let b = chunk[i];
+                    self.reader.consume(i);
+                    return Ok(Some(b));
+                }
+                None => {
+                    let n = chunk.len();
+                    self.reader.consume(n);
+                }
+            }
+        }
+    }
+
+    /// Copies the bytes of the next element into `buf`, consuming the delimiter after it.
+    ///
+    /// Stops with [`End::TooLarge`] rather than buffer more than the size limit.
+    fn scan(&mut self) -> io::Result<End> {
+        self.buf.clear();
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+
+        loop {
+            let chunk = fill_buf(&mut self.reader)?;
+            if chunk.is_empty() {
+                return Ok(End::Eof);
+            }
+            for (i, &b) in chunk.iter().enumerate() {
+                if in_string {
+                    if escaped {
+                        escaped = false;
+                    } else if b == b'\\' {
+                        escaped = true;
+                    } else if b == b'"' {
+                        in_string = false;
+                    }
+                    continue;
+                }
+                let end = match b {
+                    b'"' => {
+                        in_string = true;
+                        continue;
+                    }
+                    b'[' | b'{' => {
+                        depth += 1;
+                        continue;
+                    }
+                    b',' if depth == 0 => End::Comma,
+                    b']' if depth == 0 => End::Close,
+                    b']' | b'}' => {
+                        depth = depth.saturating_sub(1);
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if self.buf.len() + i > self.max_element_size {
+                    return Ok(End::TooLarge);
+                }
+                self.buf.extend_from_slice(&chunk[..i]);
+                self.reader.consume(i + 1);
+                return Ok(end);
+            }
+            let n = chunk.len();
+            if self.buf.len() + n > self.max_element_size {
+                return Ok(End::TooLarge);
+            }
+            self.buf.extend_from_slice(chunk);
+            self.reader.consume(n);
+        }
+    }
+
+    /// Ends the stream with an error about the array itself.
+    fn fail(&mut self, error: serde_json::Error) -> Option<Result<JsonResult<T, E>, ElementError>> {
+        self.state = State::Done;
+        Some(Err(ElementError::new(self.index, DecodeError::Json(error))))
+    }
+}
+
+impl<R, T, E> Iterator for ArrayStream<R, T, E>
+where
+    R: Read,
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    type Item = Result<JsonResult<T, E>, ElementError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.state {
+                State::Start => match self.peek() {
+                    Ok(Some(b'[')) => {
+                        self.reader.consume(1);
+                        self.state = State::First;
+                    }
+                    Ok(Some(_)) => {
+                        return self.fail(serde_json::Error::custom(
+                            "expected `[` at the start of the array",
+                        ));
+                    }
+                    Ok(None) => return self.fail(unexpected_eof()),
+                    Err(e) => return self.fail(serde_json::Error::io(e)),
+                },
+                State::First => match self.peek() {
+                    Ok(Some(b']')) => {
+                        self.reader.consume(1);
+                        self.state = State::Closed;
+                    }
+                    Ok(_) => self.state = State::Next,
+                    Err(e) => return self.fail(serde_json::Error::io(e)),
+                },
+                State::Next => {
+                    match self.peek() {
+                        Ok(Some(b']')) => {
+                            return self.fail(serde_json::Error::custom(
+                                "trailing comma before the closing `]` of the array",
+                            ));
+                        }
+                        Ok(Some(b',')) => {
+                            return self
+                                .fail(serde_json::Error::custom("expected an element before `,`"));
+                        }
+                        Ok(Some(_)) => {}
+                        Ok(None) => return self.fail(unexpected_eof()),
+                        Err(e) => return self.fail(serde_json::Error::io(e)),
+                    }
+                    let end = match self.scan() {
+                        Ok(End::TooLarge) => {
+                            let limit = self.max_element_size;
+                            return self.fail(serde_json::Error::custom(format_args!(
+                                "element is larger than the limit of {} bytes",
+                                limit
+                            )));
+                        }
+                        Ok(end) => end,
+                        Err(e) => return self.fail(serde_json::Error::io(e)),
+                    };
+                    let index = self.index;
+                    self.index += 1;
+
+                    let record = Untagged::<PreferOk>::from_slice::<T, E>(&self.buf);
+                    self.state = match end {
+                        End::Comma => State::Next,
+                        End::Close => State::Closed,
+                        End::Eof if record.is_ok() => State::Truncated,
+                        End::Eof | End::TooLarge => State::Done,
+                    };
+                    return Some(
+                        record
+                            .map(JsonResult)
+                            .map_err(|e| ElementError::new(index, e)),
+                    );
+                }
+                State::Closed => match self.peek() {
+                    Ok(None) => self.state = State::Done,
+                    Ok(Some(_)) => {
+                        return self.fail(serde_json::Error::custom(
+                            "trailing characters after the array",
+                        ));
+                    }
+                    Err(e) => return self.fail(serde_json::Error::io(e)),
+                },
+                State::Truncated => return self.fail(unexpected_eof()),
+                State::Done => return None,
+            }
+        }
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+fn unexpected_eof() -> serde_json::Error {
+    serde_json::Error::io(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "input ended before the closing `]` of the array",
+    ))
+}
+
+/// Like [`BufRead::fill_buf`], retrying reads that were interrupted.
+fn fill_buf<R: BufRead>(reader: &mut R) -> io::Result<&[u8]> {
+    loop {
+        match reader.fill_buf() {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+            Ok(_) => break,
+        }
+    }
+    reader.fill_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::ArrayStream;
+    use crate::error::DecodeError;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Item {
+        id: u64,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Failure {
+        error: String,
+    }
+
+    type Stream<R> = ArrayStream<R, Item, Failure>;
+
+    fn item(id: u64, tags: &[&str]) -> Item {
+        Item {
+            id,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    /// Hands out at most one byte per read, to split every token across reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&b, rest)) if !buf.is_empty() => {
+                    buf[0] = b;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    const MIXED: &str = r#" [
+        {"id": 1, "tags": ["a,b", "]"]},
+        {"error": "quote \" and ] inside"},
+        {"id": "2", "tags": []},
+        {"id": 3, "tags": [["nested"]]},
+        {"id": 4, "tags": ["{"]}
+    ] "#;
+
+    fn check_mixed<R: Read>(stream: Stream<R>) {
+        let results: Vec<_> = stream.collect();
+        assert_eq!(results.len(), 5);
+
+        assert_eq!(results[0].as_ref().unwrap().0, Ok(item(1, &["a,b", "]"])));
+        assert_eq!(
+            results[1].as_ref().unwrap().0,
+            Err(Failure {
+                error: "quote \" and ] inside".to_string()
+            })
+        );
+
+        let err = results[2].as_ref().unwrap_err();
+        assert_eq!(err.index(), 2);
+        assert!(matches!(err.error(), DecodeError::NoMatch(_)));
+        assert!(err.to_string().starts_with("element 2: Failed to parse as"));
+
+        let err = results[3].as_ref().unwrap_err();
+        assert_eq!(err.index(), 3);
+
+        assert_eq!(results[4].as_ref().unwrap().0, Ok(item(4, &["{"])));
+    }
+
+    #[test]
+    fn test_decodes_each_element() {
+        check_mixed(Stream::new(MIXED.as_bytes()));
+    }
+
+    #[test]
+    fn test_elements_split_across_reads() {
+        check_mixed(Stream::new(Trickle(MIXED.as_bytes())));
+    }
+
+    #[test]
+    fn test_empty_array() {
+        assert_eq!(Stream::new(" [ ] \n".as_bytes()).count(), 0);
+    }
+
+    #[test]
+    fn test_not_an_array() {
+        let mut stream = Stream::new(r#"{"id": 1}"#.as_bytes());
+
+        let err = stream.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("expected `[`"));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_truncated_after_element() {
+        let mut stream = Stream::new(r#"[{"id": 1, "tags": []},"#.as_bytes());
+
+        assert!(stream.next().unwrap().is_ok());
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert!(stream.next().is_none());
+
+        let mut stream = Stream::new(r#"[{"id": 1, "tags": []}"#.as_bytes());
+
+        assert!(stream.next().unwrap().is_ok());
+        let err = stream.next().unwrap().unwrap_err();
+        let DecodeError::Json(e) = err.error() else {
+            panic!("expected a JSON error");
+        };
+        assert!(e.is_io());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_truncated_inside_element() {
+        let mut stream = Stream::new(r#"[{"id": 1, "tags": ["a"#.as_bytes());
+
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.index(), 0);
+        let DecodeError::Json(e) = err.error() else {
+            panic!("expected a JSON error");
+        };
+        assert!(e.is_eof());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_trailing_characters() {
+        let mut stream = Stream::new(r#"[{"error": "x"}] []"#.as_bytes());
+
+        assert!(stream.next().unwrap().is_ok());
+        let err = stream.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("trailing characters"));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_trailing_comma() {
+        let mut stream = ArrayStream::<_, u32, String>::new("[1,2,]".as_bytes());
+
+        assert_eq!(stream.next().unwrap().unwrap().0, Ok(1));
+        assert_eq!(stream.next().unwrap().unwrap().0, Ok(2));
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.index(), 2);
+        assert!(matches!(err.error(), DecodeError::Json(_)));
+        assert!(err.to_string().contains("trailing comma"));
+        assert!(stream.next().is_none());
+
+        let mut stream = ArrayStream::<_, u32, String>::new("[1, ,2]".as_bytes());
+
+        assert_eq!(stream.next().unwrap().unwrap().0, Ok(1));
+        let err = stream.next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("expected an element"));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_oversized_element_ends_stream() {
+        let head = r#"[{"id": 1, "tags": []}, {"error": "unterminated "#.as_bytes();
+        let mut stream = Stream::new(head.chain(io::repeat(b'x'))).with_max_element_size(1024);
+
+        assert!(stream.next().unwrap().is_ok());
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert!(matches!(err.error(), DecodeError::Json(_)));
+        assert!(err.to_string().contains("limit of 1024 bytes"));
+        assert!(stream.next().is_none());
+        assert!(stream.buf.len() <= 1024);
+    }
+
+    #[test]
+    fn test_memory_bounded_by_element() {
+        /// Produces `[e, e, ..., e]` on demand without holding the whole array.
+        struct Generated {
+            remaining: usize,
+            pending: Vec<u8>,
+        }
+
+        impl Read for Generated {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.pending.is_empty() {
+                    self.pending = match self.remaining {
+                        0 => return Ok(0),
+                        1 => br#"{"id":7,"tags":["x"]}]"#.to_vec(),
+                        _ => br#"{"id":7,"tags":["x"]},"#.to_vec(),
+                    };
+                    self.remaining -= 1;
+                }
+                let n = buf.len().min(self.pending.len());
+                buf[..n].copy_from_slice(&self.pending[..n]);
+                self.pending.drain(..n);
+                Ok(n)
+            }
+        }
+
+        let elements = 100_000;
+        let mut stream = Stream::new(Generated {
+            remaining: elements,
+            pending: b"[".to_vec(),
+        });
+
+        let mut count = 0;
+        for element in stream.by_ref() {
+            assert_eq!(element.unwrap().0, Ok(item(7, &["x"])));
+            count += 1;
+        }
+
+        assert_eq!(count, elements);
+        assert_eq!(stream.index(), elements);
+        assert!(stream.buf.capacity() < 64);
+    }
+}
@@ -0,0 +1,384 @@
+//! Async NDJSON streams of `JsonResult` records (`tokio` feature).
+//!
+//! [`JsonResultStream`] is a [`Stream`] that decodes one record per line from a tokio
+//! [`AsyncBufRead`], such as a child process's stdout or a socket, using the same `T`-then-`E`
+//! rules as `r#struct::JsonResult` and the same line framing and size limit as
+//! `ndjson::JsonResultStream`. [`JsonResultSink`] is a [`Sink`] that writes `JsonResult` or
+//! plain `Result` values to an [`AsyncWrite`] as newline-terminated frames.
+//!
+//! ```rust
+//! use futures::{SinkExt, StreamExt};
+//! use json_result::r#async::{JsonResultSink, JsonResultStream};
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let (client, server) = tokio::io::duplex(64);
+//!
+//! let mut sink = JsonResultSink::new(client);
+//! sink.send(Ok::<u32, String>(1)).await.unwrap();
+//! sink.send(Err::<u32, String>("busy".to_string())).await.unwrap();
+//! sink.close().await.unwrap();
+//!
+//! let stream = JsonResultStream::<_, u32, String>::new(tokio::io::BufReader::new(server));
+//! let records: Vec<_> = stream.map(|r| r.unwrap().0).collect().await;
+//! assert_eq!(records, vec![Ok(1), Err("busy".to_string())]);
+//! # });
+//! ```
+
+use std::io;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+use crate::error::DecodeError;
+use crate::ndjson::LineFramer;
+use crate::repr::ResultLike;
+use crate::r#struct::JsonResult;
+
+/// Bytes the sink buffers before `poll_ready` starts writing them out.
+const SINK_BUFFER: usize = 8 * 1024;
+
+/// JsonResultStream<R, T, E>
+///
+/// Reads NDJSON records from `R` and decodes each line as `T`, falling back to `E`.
+///
+/// A line that is not JSON, fits neither type, or is over the size limit yields a
+/// [`DecodeError`], and the stream moves on to the next line; [`JsonResultStream::line`]
+/// tells which line it came from. Blank lines are skipped. An I/O error from the reader is
+/// yielded once as a [`DecodeError::Json`] and ends the stream.
+pub struct JsonResultStream<R, T, E> {
+    reader: R,
+    framer: LineFramer,
+    marker: PhantomData<fn() -> (T, E)>,
+}
+
+impl<R, T, E> JsonResultStream<R, T, E>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    /// Creates a stream over `reader`.
+    pub fn new(reader: R) -> Self {
+        JsonResultStream {
+            reader,
+            framer: LineFramer::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets the longest line, in bytes and without its newline, the stream buffers before
+    /// giving up on it. Defaults to [`DEFAULT_MAX_LINE_SIZE`](crate::ndjson::DEFAULT_MAX_LINE_SIZE).
+    ///
+    /// A longer line yields a [`DecodeError`], and the rest of it is discarded unread.
+    pub fn with_max_line_size(mut self, bytes: usize) -> Self {
+        self.framer.set_max_line_size(bytes);
+        self
+    }
+
+    /// Number of lines read so far, including blank ones. After an item is yielded, this is
+    /// the line it came from.
+    pub fn line(&self) -> usize {
+        self.framer.line()
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, T, E> Stream for JsonResultStream<R, T, E>
+where
+    R: AsyncBufRead + Unpin,
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    type Item = Result<JsonResult<T, E>, DecodeError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.framer.is_done() {
+            let chunk = match ready!(Pin::new(&mut this.reader).poll_fill_buf(cx)) {
+                Ok(chunk) => chunk,
+                Err(e) => return Poll::Ready(Some(Err(this.framer.fail(e).1))),
+            };
+            let (used, end) = this.framer.feed(chunk);
+            Pin::new(&mut this.reader).consume(used);
+            if let Some(record) = end.and_then(|end| this.framer.record(end)) {
+                return Poll::Ready(Some(record));
+            }
+        }
+        Poll::Ready(None)
+    }
+}
+
+/// JsonResultSink<W, J>
+///
+/// Writes `J` values to `W`, serializing whichever branch is present followed by a newline. `J`
+/// is either `JsonResult` flavour or a plain `Result`, and is usually inferred from the first
+/// value sent.
+///
+/// Frames are buffered and written once the buffer fills up or the sink is flushed, so call
+/// `flush` or `close` when done.
+pub struct JsonResultSink<W, J> {
+    writer: W,
+    buf: Vec<u8>,
+    written: usize,
+    marker: PhantomData<fn(J)>,
+}
+
+impl<W: AsyncWrite + Unpin, J> JsonResultSink<W, J> {
+    /// Creates a sink over `writer`.
+    pub fn new(writer: W) -> Self {
+        JsonResultSink {
+            writer,
+            buf: Vec::new(),
+            written: 0,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the underlying writer. Frames that were not flushed are lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes out the buffered frames.
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.written < self.buf.len() {
+            let n = ready!(Pin::new(&mut self.writer).poll_write(cx, &self.buf[self.written..]))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.written += n;
+        }
+        self.buf.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W, J> Sink<J> for JsonResultSink<W, J>
+where
+    W: AsyncWrite + Unpin,
+    J: ResultLike,
+    J::Ok: Serialize,
+    J::Err: Serialize,
+{
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.buf.len() >= SINK_BUFFER {
+            ready!(this.poll_write_buf(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: J) -> io::Result<()> {
+        let this = self.get_mut();
+        match item.as_result() {
+            Ok(v) => serde_json::to_writer(&mut this.buf, v),
+            Err(e) => serde_json::to_writer(&mut this.buf, e),
+        }?;
+        this.buf.push(b'\n');
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.writer).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.writer).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+    use serde::{Deserialize, Serialize};
+    use tokio::io::{AsyncWriteExt, BufReader};
+
+    use super::{JsonResultSink, JsonResultStream};
+    use crate::error::DecodeError;
+    use crate::repr::{PreferOk, Untagged};
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Progress {
+        done: u32,
+        total: u32,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Failure {
+        error: String,
+    }
+
+    type Stream<R> = JsonResultStream<R, Progress, Failure>;
+
+    fn progress(done: u32) -> Progress {
+        Progress { done, total: 10 }
+    }
+
+    #[tokio::test]
+    async fn test_sink_to_stream_over_duplex() {
+        // A small pipe forces both sides to wait on each other mid-frame.
+        let (client, server) = tokio::io::duplex(16);
+
+        let writer = tokio::spawn(async move {
+            let mut sink = JsonResultSink::new(client);
+            for done in 0..100 {
+                sink.feed(r#struct::JsonResult(Ok(progress(done))))
+                    .await
+                    .unwrap();
+            }
+            sink.send(r#struct::JsonResult(Err(Failure {
+                error: "killed".to_string(),
+            })))
+            .await
+            .unwrap();
+            sink.close().await.unwrap();
+        });
+
+        let records: Vec<_> = Stream::new(BufReader::new(server))
+            .map(|r| r.unwrap().0)
+            .collect()
+            .await;
+        writer.await.unwrap();
+
+        assert_eq!(records.len(), 101);
+        assert_eq!(records[42], Ok(progress(42)));
+        assert_eq!(
+            records[100],
+            Err(Failure {
+                error: "killed".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bad_lines_do_not_stop_the_stream() {
+        let (mut client, server) = tokio::io::duplex(64);
+
+        client
+            .write_all(
+                b"{\"done\":1,\"total\":10}\n\n{\"done\":\"x\"}\nnot json\r\n{\"error\":\"late\"}",
+            )
+            .await
+            .unwrap();
+        drop(client);
+
+        let mut stream = Stream::new(BufReader::new(server));
+
+        assert_eq!(stream.next().await.unwrap().unwrap().0, Ok(progress(1)));
+
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, DecodeError::NoMatch(_)));
+        assert!(err.to_string().starts_with("Failed to parse as"));
+        assert_eq!(stream.line(), 3);
+
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(err, DecodeError::Json(_)));
+        assert_eq!(stream.line(), 4);
+
+        let last = stream.next().await.unwrap().unwrap();
+        assert!(matches!(last.0, Err(Failure { .. })));
+        assert_eq!(stream.line(), 5);
+
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_matches_struct_decoding() {
+        let lines = [
+            r#"{"done":3,"total":10}"#,
+            r#"{"error":"nope"}"#,
+            r#"{"done":3}"#,
+            r#"[1,2]"#,
+        ];
+        let (mut client, server) = tokio::io::duplex(1024);
+        client.write_all(lines.join("\n").as_bytes()).await.unwrap();
+        drop(client);
+
+        let streamed: Vec<_> = Stream::new(BufReader::new(server)).collect().await;
+
+        for (line, streamed) in lines.iter().zip(streamed) {
+            let direct = Untagged::<PreferOk>::from_str::<Progress, Failure>(line);
+            match (streamed, direct) {
+                (Ok(a), Ok(b)) => assert_eq!(a.0, b),
+                (Err(a), Err(b)) => assert_eq!(a.to_string(), b.to_string()),
+                (a, b) => panic!("{line}: stream gave {a:?}, struct gave {b:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_matches_blocking_stream() {
+        let input = concat!(
+            "{\"done\":1,\"total\":10}\n",
+            "{\"done\":\"far too long for the limit\"}\n",
+            "\n",
+            "{\"done\":2}\n",
+            "{\"error\":\"x\"}",
+        );
+        let (mut client, server) = tokio::io::duplex(8);
+        let writer = tokio::spawn(async move {
+            client.write_all(input.as_bytes()).await.unwrap();
+        });
+
+        let streamed: Vec<_> = Stream::new(BufReader::with_capacity(4, server))
+            .with_max_line_size(24)
+            .collect()
+            .await;
+        writer.await.unwrap();
+
+        let blocking: Vec<_> =
+            crate::ndjson::JsonResultStream::<_, Progress, Failure>::new(input.as_bytes())
+                .with_max_line_size(24)
+                .collect();
+
+        assert_eq!(streamed.len(), 4);
+        assert_eq!(streamed.len(), blocking.len());
+        for (a, b) in streamed.into_iter().zip(blocking) {
+            match (a, b) {
+                (Ok(a), Ok(b)) => assert_eq!(a.0, b.0),
+                (Err(a), Err(b)) => assert_eq!(a.to_string(), b.error().to_string()),
+                (a, b) => panic!("async gave {a:?}, blocking gave {b:?}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sink_writes_enum_and_plain_results() {
+        let (client, mut server) = tokio::io::duplex(1024);
+
+        let mut sink = JsonResultSink::new(client);
+        sink.send(r#enum::JsonResult::<u8, String>::Ok(1))
+            .await
+            .unwrap();
+        sink.send(r#enum::JsonResult::Err("two".to_string()))
+            .await
+            .unwrap();
+        let mut sink = JsonResultSink::new(sink.into_inner());
+        sink.send(Err::<u8, _>(3)).await.unwrap();
+        sink.close().await.unwrap();
+
+        let mut out = String::new();
+        tokio::io::AsyncReadExt::read_to_string(&mut server, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(out, "1\n\"two\"\n3\n");
+    }
+}
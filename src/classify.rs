@@ -0,0 +1,431 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::convert::Infallible;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::DecodeError;
+use crate::repr::untagged::{self, Ambiguity, Buffered};
+use crate::repr::{Representation, ResultLike};
+use crate::tape::Tape;
+
+/// The branch a [`Classifier`] picks for a payload.
+///
+/// Serialized as `"ok"`, `"err"` and `"either"` (with `"error"` accepted for `Err`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Branch {
+    /// Deserialize the payload as `T`.
+    Ok,
+    /// Deserialize the payload as `E`.
+    #[serde(alias = "error")]
+    Err,
+    /// Fall back to the untagged rule: try `T`, then `E`.
+    Either,
+}
+
+/// Picks the branch of a `JsonResult` by looking at the buffered payload.
+///
+/// # Examples
+///
+/// ```rust
+/// use json_result::classify::{Branch, Classifier};
+/// use serde_json::Value;
+///
+/// /// `code == 0` means success.
+/// #[derive(Default)]
+/// struct ZeroCode;
+///
+/// impl Classifier for ZeroCode {
+///     type Error = std::convert::Infallible;
+///
+///     fn classify(&self, value: &Value) -> Result<Branch, Self::Error> {
+///         Ok(if value["code"] == 0 { Branch::Ok } else { Branch::Err })
+///     }
+/// }
+/// ```
+pub trait Classifier {
+    /// Why the classifier refused a payload.
+    type Error: fmt::Display;
+
+    /// Picks the branch for `value`, or refuses it.
+    fn classify(&self, value: &Value) -> Result<Branch, Self::Error>;
+
+    /// Describes why `value` got the branch it did, for error messages when that branch then
+    /// fails to parse. Returns `None` by default.
+    fn explain(&self, value: &Value) -> Option<String> {
+        let _ = value;
+        None
+    }
+}
+
+impl<C: Classifier + ?Sized> Classifier for &C {
+    type Error = C::Error;
+
+    fn classify(&self, value: &Value) -> Result<Branch, Self::Error> {
+        (**self).classify(value)
+    }
+
+    fn explain(&self, value: &Value) -> Option<String> {
+        (**self).explain(value)
+    }
+}
+
+/// A [`Classifier`] built from a closure by [`from_fn`].
+#[derive(Clone, Copy)]
+pub struct FromFn<F>(F);
+
+/// Creates a [`Classifier`] from a closure.
+///
+/// ```rust
+/// use json_result::classify::{self, Branch};
+/// use json_result::r#struct::JsonResult;
+///
+/// let by_code = classify::from_fn(|v| match v["code"].as_i64() {
+///     Some(0) => Branch::Ok,
+///     Some(_) => Branch::Err,
+///     None => Branch::Either,
+/// });
+///
+/// let jr: JsonResult<serde_json::Value, serde_json::Value> =
+///     classify::from_value(serde_json::json!({ "code": 3 }), &by_code).unwrap();
+/// assert!(jr.is_err());
+/// ```
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: Fn(&Value) -> Branch,
+{
+    FromFn(f)
+}
+
+impl<F> Classifier for FromFn<F>
+where
+    F: Fn(&Value) -> Branch,
+{
+    type Error = Infallible;
+
+    fn classify(&self, value: &Value) -> Result<Branch, Self::Error> {
+        Ok((self.0)(value))
+    }
+}
+
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromFn").finish_non_exhaustive()
+    }
+}
+
+/// Decodes `value` into a `JsonResult` (either flavour) using the branch chosen by `classifier`.
+pub fn from_value<J, C>(value: Value, classifier: &C) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: DeserializeOwned,
+    J::Err: DeserializeOwned,
+    C: Classifier + ?Sized,
+{
+    decode(&value, &value, classifier).map(J::from_result)
+}
+
+/// Decodes `input` into the branch `classifier` picks from `value`, its [`Value`] form.
+pub(crate) fn decode<'de, T, E, B, C>(
+    input: B,
+    value: &Value,
+    classifier: &C,
+) -> Result<Result<T, E>, DecodeError>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+    B: Buffered<'de>,
+    C: Classifier + ?Sized,
+{
+    let branch = classifier
+        .classify(value)
+        .map_err(|e| DecodeError::Unclassified(e.to_string()))?;
+    let reason = match branch {
+        Branch::Either => None,
+        _ => classifier.explain(value),
+    };
+
+    match branch {
+        Branch::Ok => input
+            .attempt()
+            .map(Ok)
+            .map_err(|e| mismatch::<T>("Ok", reason, e)),
+        Branch::Err => input
+            .attempt()
+            .map(Err)
+            .map_err(|e| mismatch::<E>("Err", reason, e)),
+        Branch::Either => untagged::decode(input, Ambiguity::PreferOk),
+    }
+}
+
+/// Records the input once, so that the branch can borrow from it after the classifier has
+/// looked at its [`Value`] form.
+fn decode_from<'de, T, E, D, C>(deserializer: D, classifier: &C) -> Result<Result<T, E>, D::Error>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+    D: Deserializer<'de>,
+    C: Classifier + ?Sized,
+{
+    let tape = Tape::deserialize(deserializer)?;
+    let value = tape.to_value();
+    decode(tape, &value, classifier).map_err(de::Error::custom)
+}
+
+fn mismatch<V>(branch: &str, reason: Option<String>, e: serde_json::Error) -> DecodeError {
+    let reason = reason.map(|r| format!(" ({})", r)).unwrap_or_default();
+
+    DecodeError::Mismatch(de::Error::custom(format!(
+        "Classified as {}{} but failed to parse as {}: {}",
+        branch,
+        reason,
+        core::any::type_name::<V>(),
+        e
+    )))
+}
+
+/// ClassifySeed<'c, J, C>
+///
+/// A [`DeserializeSeed`] that deserializes a `JsonResult` (either flavour) using a classifier
+/// chosen at runtime, such as a closure from [`from_fn`].
+///
+/// ```rust
+/// use json_result::classify::{self, Branch, ClassifySeed};
+/// use json_result::r#enum::JsonResult;
+/// use serde::de::DeserializeSeed;
+///
+/// let by_ok_flag = classify::from_fn(|v| {
+///     if v["ok"] == true { Branch::Ok } else { Branch::Err }
+/// });
+///
+/// let mut de = serde_json::Deserializer::from_str(r#"{"ok":false,"reason":"busy"}"#);
+/// let jr = ClassifySeed::<JsonResult<serde_json::Value, serde_json::Value>, _>::new(&by_ok_flag)
+///     .deserialize(&mut de)
+///     .unwrap();
+/// assert!(matches!(jr, JsonResult::Err(_)));
+/// ```
+pub struct ClassifySeed<'c, J, C: ?Sized> {
+    classifier: &'c C,
+    marker: PhantomData<fn() -> J>,
+}
+
+impl<'c, J, C: ?Sized> ClassifySeed<'c, J, C> {
+    /// Creates a seed that classifies payloads with `classifier`.
+    pub fn new(classifier: &'c C) -> Self {
+        ClassifySeed {
+            classifier,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, J, C> DeserializeSeed<'de> for ClassifySeed<'_, J, C>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    C: Classifier + ?Sized,
+{
+    type Value = J;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        decode_from(deserializer, self.classifier).map(J::from_result)
+    }
+}
+
+/// Classified<C>
+///
+/// Untagged on the wire, with the branch picked by the classifier `C` rather than by trying
+/// `T` first. Use this with [`Repr`](crate::repr::Repr) when the classifier is known at the
+/// type level; use [`ClassifySeed`] when it is only known at runtime.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Classified<C>(PhantomData<C>);
+
+impl<C: Classifier + Default> Representation for Classified<C> {
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer,
+    {
+        match result {
+            Ok(v) => v.serialize(serializer),
+            Err(e) => e.serialize(serializer),
+        }
+    }
+
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        decode_from(deserializer, &C::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeSeed;
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+
+    use super::{Branch, Classified, Classifier, ClassifySeed, from_fn, from_value};
+    use crate::error::DecodeError;
+    use crate::repr::test_support::{EnumRepr, StructRepr};
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Data {
+        code: i64,
+        #[serde(default)]
+        items: Vec<u32>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Failure {
+        code: i64,
+        #[serde(default)]
+        message: Option<String>,
+    }
+
+    #[derive(Default)]
+    struct ZeroCode;
+
+    impl Classifier for ZeroCode {
+        type Error = String;
+
+        fn classify(&self, value: &Value) -> Result<Branch, Self::Error> {
+            match value.get("code").and_then(Value::as_i64) {
+                Some(0) => Ok(Branch::Ok),
+                Some(_) => Ok(Branch::Err),
+                None => Err("missing `code`".to_string()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_type_level_classifier_ok() {
+        let json = serde_json::json!({ "code": 0, "items": [1] });
+        let jr: StructRepr<Data, Failure, Classified<ZeroCode>> =
+            serde_json::from_value(json).unwrap();
+
+        assert_eq!(
+            jr.0.0,
+            Ok(Data {
+                code: 0,
+                items: vec![1]
+            })
+        );
+    }
+
+    #[test]
+    fn test_type_level_classifier_err_even_if_t_fits() {
+        // Data would accept this payload; the classifier says otherwise
+        let json = serde_json::json!({ "code": 7 });
+        let jr = EnumRepr::<Data, Failure, Classified<ZeroCode>>::try_from(json).unwrap();
+
+        assert!(matches!(
+            jr.0,
+            r#enum::JsonResult::Err(Failure { code: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn test_classifier_refusal() {
+        let json = serde_json::json!({ "items": [] });
+        let msg = StructRepr::<Data, Failure, Classified<ZeroCode>>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("Unclassified payload: missing `code`"));
+    }
+
+    #[test]
+    fn test_chosen_branch_mismatch() {
+        let json = serde_json::json!({ "code": 0, "items": "nope" });
+        let err =
+            from_value::<r#struct::JsonResult<Data, Failure>, _>(json, &ZeroCode).unwrap_err();
+
+        assert!(matches!(err, DecodeError::Mismatch(_)));
+        let msg = err.to_string();
+        assert!(msg.contains("Classified as Ok"));
+        assert!(msg.contains("Data"));
+    }
+
+    #[test]
+    fn test_closure_either_falls_back_to_untagged() {
+        let classifier = from_fn(|_: &Value| Branch::Either);
+
+        let jr: r#struct::JsonResult<u32, String> =
+            from_value(serde_json::json!("boom"), &classifier).unwrap();
+        assert_eq!(jr.0, Err("boom".to_string()));
+
+        let err =
+            from_value::<r#struct::JsonResult<u32, String>, _>(serde_json::json!([1]), &classifier)
+                .unwrap_err();
+        assert!(matches!(err, DecodeError::NoMatch(_)));
+    }
+
+    #[test]
+    fn test_seed_with_runtime_closure() {
+        let threshold = 400;
+        let by_status = from_fn(move |v: &Value| match v["status"].as_u64() {
+            Some(s) if s < threshold => Branch::Ok,
+            _ => Branch::Err,
+        });
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"status":200,"body":"hi"}"#);
+        let jr = ClassifySeed::<r#enum::JsonResult<Value, Value>, _>::new(&by_status)
+            .deserialize(&mut de)
+            .unwrap();
+        assert!(matches!(jr, r#enum::JsonResult::Ok(_)));
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"status":503}"#);
+        let jr = ClassifySeed::<r#enum::JsonResult<Value, Value>, _>::new(&by_status)
+            .deserialize(&mut de)
+            .unwrap();
+        assert!(matches!(jr, r#enum::JsonResult::Err(_)));
+    }
+
+    #[test]
+    fn test_seed_with_trait_object() {
+        let classifier: &dyn Classifier<Error = String> = &ZeroCode;
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"code":2,"message":"x"}"#);
+        let jr = ClassifySeed::<r#struct::JsonResult<Data, Failure>, _>::new(classifier)
+            .deserialize(&mut de)
+            .unwrap();
+
+        assert_eq!(
+            jr.0,
+            Err(Failure {
+                code: 2,
+                message: Some("x".into())
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialization_is_untagged() {
+        let jr = StructRepr::<Data, Failure, Classified<ZeroCode>>::new(r#struct::JsonResult(Err(
+            Failure {
+                code: 1,
+                message: None,
+            },
+        )));
+
+        assert_eq!(
+            serde_json::to_value(&jr).unwrap(),
+            serde_json::json!({ "code": 1, "message": null })
+        );
+    }
+}
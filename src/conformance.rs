@@ -0,0 +1,84 @@
+//! Conformance suite shared by the alternative JSON backends.
+//!
+//! Each backend decodes the same inputs through its text and value entry points, and both must
+//! pick the same branch as `serde_json`, produce the same value, or fail with the same message.
+
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::DecodeError;
+use crate::r#struct;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct User {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) email: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct ApiError {
+    pub(crate) code: i32,
+    pub(crate) message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Amb {
+    pub(crate) x: u32,
+}
+
+/// A backend under test.
+pub(crate) trait Backend {
+    /// Decodes `input` from its text.
+    fn from_text<T, E>(input: &str) -> Result<Result<T, E>, DecodeError>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned;
+
+    /// Parses `input` into the backend's own value type and decodes that.
+    fn from_value<T, E>(input: &str) -> Result<Result<T, E>, DecodeError>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned;
+}
+
+/// Runs every case against `B`.
+pub(crate) fn check<B: Backend>() {
+    conform::<B, User, ApiError>(r#"{"id":1,"name":"ann","email":null}"#);
+    conform::<B, User, ApiError>(r#"{"id":1,"name":"ann"}"#);
+    conform::<B, User, ApiError>(r#"{"code":-1,"message":"nope"}"#);
+    conform::<B, User, ApiError>(r#"{"id":"1","name":"ann"}"#);
+    conform::<B, User, ApiError>(r#"{"nmae":"ann","mesage":"x"}"#);
+    conform::<B, User, ApiError>(r#"[1,2,3]"#);
+    conform::<B, User, ApiError>("null");
+    conform::<B, Amb, Amb>(r#"{"x":10}"#);
+    conform::<B, Vec<User>, ApiError>(r#"[{"id":1,"name":"a"},{"id":2,"name":3}]"#);
+    conform::<B, u64, String>("18446744073709551615");
+    conform::<B, i64, f64>("-12");
+    conform::<B, i64, f64>("1.5");
+    conform::<B, String, u32>(r#""esc\"aped\n""#);
+    conform::<B, bool, ()>("true");
+}
+
+fn conform<B, T, E>(input: &str)
+where
+    B: Backend,
+    T: DeserializeOwned + Debug + PartialEq,
+    E: DeserializeOwned + Debug + PartialEq,
+{
+    let reference = serde_json::from_str::<r#struct::JsonResult<T, E>>(input);
+
+    for decoded in [B::from_text::<T, E>(input), B::from_value::<T, E>(input)] {
+        match &reference {
+            Ok(expected) => assert_eq!(&decoded.unwrap(), &expected.0, "{}", input),
+            Err(expected) => assert_eq!(
+                decoded.unwrap_err().to_string(),
+                expected.to_string(),
+                "{}",
+                input
+            ),
+        }
+    }
+}
@@ -0,0 +1,438 @@
+//! Failure diagnostics for untagged decoding.
+//!
+//! When neither branch parses, each branch is replayed over the recorded input with a
+//! watch that notes the innermost struct it failed in. Comparing the fields that struct
+//! declares with the keys the payload has there lets the error list missing and unexpected
+//! fields and say which branch came closer. The failure path itself comes from the replay in
+//! `repr::untagged`, which tracks it with `serde_path_to_error`.
+//!
+//! The watched replay fails with an error type that keeps the missing field serde reports, so
+//! no message is parsed. serde only reports the first required field it misses; the declared
+//! fields after it that the payload also lacks are listed with it, without running the branch
+//! again.
+//!
+//! Replays only happen on the failure path; successful decodes never touch this module.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use serde::de::{self, Deserialize};
+use serde_path_to_error::Segment;
+
+use crate::error::render_path;
+use crate::tape::{Tape, Token, Watch, skip};
+
+/// How far one branch got before failing, and what the payload lacked or had extra.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnosis {
+    path: String,
+    location: String,
+    depth: usize,
+    entered: bool,
+    matched: usize,
+    missing: Vec<&'static str>,
+    unexpected: Vec<String>,
+    suggestions: Vec<Suggestion>,
+}
+
+impl Diagnosis {
+    /// Where the branch failed, e.g. `.items[3].price`, or `.` for the root.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The object the field lists below refer to: the failing object itself for a missing
+    /// field, or the nearest enclosing object for any other failure.
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Number of path segments the branch descended before failing.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Number of keys at [`Diagnosis::location`] the branch recognised.
+    pub fn matched(&self) -> usize {
+        self.matched
+    }
+
+    /// Fields absent at [`Diagnosis::location`], in declaration order, when the branch failed
+    /// for lack of one. The first is the required field serde reported; the rest are later
+    /// declared fields the payload also lacks, which may include optional ones.
+    pub fn missing(&self) -> &[&'static str] {
+        &self.missing
+    }
+
+    /// Keys at [`Diagnosis::location`] the branch does not know.
+    pub fn unexpected(&self) -> &[String] {
+        &self.unexpected
+    }
+
+    /// Likely misspellings among [`Diagnosis::unexpected`]. Always empty without the `std`
+    /// feature.
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
+    }
+
+    /// Orders two diagnoses by how close their branch came to parsing: deeper failures first,
+    /// then branches that got as far as reading an object as a struct, then more recognised
+    /// keys, then fewer missing and unexpected fields.
+    pub(crate) fn closeness(&self) -> (usize, bool, usize, isize, isize) {
+        (
+            self.depth,
+            self.entered,
+            self.matched,
+            -(self.missing.len() as isize),
+            -(self.unexpected.len() as isize),
+        )
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.missing.is_empty() {
+            write!(f, "missing ")?;
+            for (i, field) in self.missing.iter().enumerate() {
+                let sep = if i == 0 { "" } else { ", " };
+                write!(f, "{}`{}`", sep, field)?;
+            }
+        }
+        if !self.unexpected.is_empty() {
+            let sep = if self.missing.is_empty() { "" } else { "; " };
+            write!(f, "{}unexpected ", sep)?;
+            for (i, key) in self.unexpected.iter().enumerate() {
+                let sep = if i == 0 { "" } else { ", " };
+                write!(f, "{}`{}`", sep, key)?;
+                if let Some(s) = self.suggestions.iter().find(|s| &s.found == key) {
+                    write!(f, " (did you mean `{}`?)", s.expected)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An unexpected key that looks like a misspelling of an expected field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    found: String,
+    expected: &'static str,
+}
+
+impl Suggestion {
+    /// The key found in the payload.
+    pub fn found(&self) -> &str {
+        &self.found
+    }
+
+    /// The expected field it most resembles.
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+}
+
+/// Similarity above which an unexpected key is reported as a likely misspelling.
+#[cfg(feature = "std")]
+const SIMILARITY: f64 = 0.8;
+
+/// Explains why `V` fails on `tape`, given the `path` where it failed.
+///
+/// Returns an empty diagnosis if the replay unexpectedly succeeds.
+pub(crate) fn diagnose<'de, V: Deserialize<'de>>(tape: &Tape<'de>, path: &[Segment]) -> Diagnosis {
+    let mut diagnosis = Diagnosis {
+        path: render_path(path),
+        location: ".".to_string(),
+        depth: path.len(),
+        ..Diagnosis::default()
+    };
+    let watch = Watch::default();
+    let Err(error) = V::deserialize(tape.watched::<Failure>(&watch)) else {
+        return diagnosis;
+    };
+    let Some(object) = watch.failed() else {
+        return diagnosis;
+    };
+
+    diagnosis.entered = true;
+    diagnosis.location = match resolve(tape.tokens(), path, object.pos) {
+        Some(depth) => render_path(&path[..depth]),
+        None => diagnosis.path.clone(),
+    };
+
+    let keys = keys(tape.tokens(), object.pos);
+    if let Failure::Missing(field) = error {
+        // Absent fields declared before the reported one were optional, or serde would have
+        // reported them instead.
+        if let Some(first) = object.fields.iter().position(|f| *f == field) {
+            diagnosis.missing = object.fields[first..]
+                .iter()
+                .copied()
+                .filter(|f| !keys.contains(f))
+                .collect();
+        }
+    }
+
+    for key in &keys {
+        if object.fields.contains(key) {
+            diagnosis.matched += 1;
+        } else if !diagnosis.unexpected.iter().any(|k| k == key) {
+            diagnosis.unexpected.push(key.to_string());
+        }
+    }
+
+    // "Did you mean" hints need strsim, which requires std.
+    #[cfg(feature = "std")]
+    for key in &diagnosis.unexpected {
+        let best = object
+            .fields
+            .iter()
+            .filter(|f| !keys.contains(f))
+            .map(|f| (strsim::jaro_winkler(key, f), *f))
+            .filter(|(score, _)| *score >= SIMILARITY)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, expected)) = best {
+            diagnosis.suggestions.push(Suggestion {
+                found: key.clone(),
+                expected,
+            });
+        }
+    }
+
+    diagnosis
+}
+
+/// The error of a watched replay. Keeps the missing field serde reports as the field itself and
+/// drops every other error, which the diagnosis does not need.
+#[derive(Debug)]
+enum Failure {
+    Missing(&'static str),
+    Other,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Missing(field) => write!(f, "missing field `{}`", field),
+            Failure::Other => f.write_str("replay failed"),
+        }
+    }
+}
+
+impl core::error::Error for Failure {}
+
+impl de::Error for Failure {
+    fn custom<T: fmt::Display>(_msg: T) -> Self {
+        Failure::Other
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Failure::Missing(field)
+    }
+}
+
+/// The keys of the object at `pos`; non-string keys read as `?`.
+fn keys<'a>(tokens: &'a [Token<'_>], pos: usize) -> Vec<&'a str> {
+    let Token::Map { len, .. } = tokens[pos] else {
+        return Vec::new();
+    };
+    let mut keys = Vec::with_capacity(len);
+    let mut key = pos + 1;
+    for _ in 0..len {
+        keys.push(match &tokens[key] {
+            Token::Str(s) => s.as_ref(),
+            _ => "?",
+        });
+        key = skip(tokens, skip(tokens, key));
+    }
+    keys
+}
+
+/// How many segments of `path` lead to the value at `target`, if some prefix of it does.
+fn resolve(tokens: &[Token<'_>], path: &[Segment], target: usize) -> Option<usize> {
+    fn unwrap(tokens: &[Token<'_>], pos: usize) -> usize {
+        match tokens[pos] {
+            Token::Some => unwrap(tokens, pos + 1),
+            _ => pos,
+        }
+    }
+
+    let mut pos = unwrap(tokens, 0);
+    let mut found = (pos == target).then_some(0);
+    for (depth, segment) in path.iter().enumerate() {
+        let next = match (segment, &tokens[pos]) {
+            (Segment::Seq { index }, Token::Seq { len, .. }) if index < len => {
+                Some((0..*index).fold(pos + 1, |p, _| skip(tokens, p)))
+            }
+            (Segment::Map { key } | Segment::Enum { variant: key }, Token::Map { len, .. }) => {
+                let mut entry = pos + 1;
+                let mut value = None;
+                for _ in 0..*len {
+                    if matches!(&tokens[entry], Token::Str(k) if k == key) {
+                        value = Some(skip(tokens, entry));
+                        break;
+                    }
+                    entry = skip(tokens, skip(tokens, entry));
+                }
+                value
+            }
+            _ => None,
+        };
+        let Some(next) = next else {
+            break;
+        };
+        pos = unwrap(tokens, next);
+        if pos == target {
+            found = Some(depth + 1);
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{Value, json};
+
+    use super::Diagnosis;
+    use crate::tape::Tape;
+
+    fn diagnose<V: DeserializeOwned>(value: &Value) -> Diagnosis {
+        let tape = Tape::from(value);
+        let path = match serde_path_to_error::deserialize::<_, V>(tape.replay()) {
+            Err(e) => e.path().iter().cloned().collect(),
+            Ok(_) => Vec::new(),
+        };
+        super::diagnose::<V>(&tape, &path)
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct User {
+        id: u32,
+        name: String,
+        email: String,
+        nickname: Option<String>,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Order {
+        items: Vec<Item>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Item {
+        sku: String,
+        price: u32,
+    }
+
+    #[test]
+    fn test_lists_absent_fields_from_the_first_required_one() {
+        let d = diagnose::<User>(&json!({ "id": 1 }));
+
+        assert_eq!(d.path(), ".");
+        assert_eq!(d.location(), ".");
+        assert_eq!(d.missing(), &["name", "email", "nickname", "tags"]);
+        assert!(d.unexpected().is_empty());
+        assert_eq!(d.matched(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_suggests_misspelled_keys() {
+        let d = diagnose::<User>(&json!({ "id": 1, "name": "a", "emial": "x", "extra": true }));
+
+        assert_eq!(d.missing(), &["email", "nickname", "tags"]);
+        assert_eq!(d.unexpected(), &["emial".to_string(), "extra".to_string()]);
+        assert_eq!(d.suggestions().len(), 1);
+        assert_eq!(d.suggestions()[0].found(), "emial");
+        assert_eq!(d.suggestions()[0].expected(), "email");
+
+        assert_eq!(
+            d.to_string(),
+            "missing `email`, `nickname`, `tags`; unexpected `emial` (did you mean `email`?), `extra`"
+        );
+    }
+
+    #[test]
+    fn test_nested_missing_field() {
+        let d = diagnose::<Order>(&json!({
+            "items": [{ "sku": "a", "price": 1 }, { "sku": "b", "prize": 2 }]
+        }));
+
+        assert_eq!(d.path(), ".items[1]");
+        assert_eq!(d.depth(), 2);
+        assert_eq!(d.missing(), &["price"]);
+        #[cfg(feature = "std")]
+        assert_eq!(d.suggestions()[0].expected(), "price");
+    }
+
+    #[test]
+    fn test_type_error_reports_enclosing_object() {
+        let d = diagnose::<Order>(&json!({ "items": [{ "sku": "a", "price": "free" }] }));
+
+        assert_eq!(d.path(), ".items[0].price");
+        assert_eq!(d.location(), ".items[0]");
+        assert_eq!(d.depth(), 3);
+        assert!(d.missing().is_empty());
+        assert_eq!(d.matched(), 2);
+    }
+
+    #[test]
+    fn test_missing_struct_field_is_filled() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Envelope {
+            order: Order,
+            user: User,
+            id: u64,
+        }
+
+        let d = diagnose::<Envelope>(&json!({}));
+        assert_eq!(d.missing(), &["order", "user", "id"]);
+    }
+
+    #[test]
+    fn test_skips_optional_fields_before_the_reported_one() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Profile {
+            bio: Option<String>,
+            #[serde(default)]
+            age: u8,
+            handle: String,
+            avatar: Option<String>,
+        }
+
+        let d = diagnose::<Profile>(&json!({}));
+        assert_eq!(d.missing(), &["handle", "avatar"]);
+    }
+
+    #[test]
+    fn test_non_object_payload() {
+        let d = diagnose::<User>(&json!("text"));
+
+        assert_eq!(d.path(), ".");
+        assert!(d.missing().is_empty());
+        assert_eq!(d.matched(), 0);
+    }
+
+    #[test]
+    fn test_deny_unknown_fields() {
+        #[derive(Debug, Serialize, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Strict {
+            value: u32,
+        }
+
+        let d = diagnose::<Strict>(&json!({ "valeu": 1 }));
+        assert_eq!(d.unexpected(), &["valeu".to_string()]);
+        #[cfg(feature = "std")]
+        assert_eq!(d.suggestions()[0].expected(), "value");
+    }
+}
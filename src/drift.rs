@@ -0,0 +1,468 @@
+//! Reporting the fields a successful decode ignored, to catch upstream schema drift early.
+//!
+//! When an upstream API adds fields, decoding a `JsonResult` keeps succeeding and the new
+//! fields are dropped without a trace. `from_str_reported` and `from_value_reported` on both
+//! `JsonResult` flavours, and [`Reported`] for use inside derived types, keep the JSON paths of
+//! the keys the chosen branch skipped. The `*_with` constructors of [`Reported`] also pass each
+//! decode that skipped keys to a callback and, with the `tracing` feature, every such decode is
+//! emitted as a `WARN` event with target `json_result::drift`.
+//!
+//! ```rust
+//! use json_result::r#struct::JsonResult;
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Balance {
+//!     amount: u64,
+//! }
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct ApiError {
+//!     error: String,
+//! }
+//!
+//! let body = r#"{"amount": 12, "currency": "EUR"}"#;
+//! let reported = JsonResult::<Balance, ApiError>::from_str_reported(body).unwrap();
+//!
+//! assert_eq!(reported.ignored(), [".currency"]);
+//! assert_eq!(reported.into_inner().0.unwrap().amount, 12);
+//! ```
+//!
+//! With a callback:
+//!
+//! ```rust
+//! use json_result::drift::Reported;
+//! use json_result::r#enum::JsonResult;
+//!
+//! # #[derive(Debug, serde::Deserialize)]
+//! # struct Balance {
+//! #     amount: u64,
+//! # }
+//! # #[derive(Debug, serde::Deserialize)]
+//! # struct ApiError {
+//! #     error: String,
+//! # }
+//! let mut drifted = Vec::new();
+//! let body = r#"{"error": "closed", "code": 7}"#;
+//! Reported::<JsonResult<Balance, ApiError>>::from_str_with(body, |drift| {
+//!     drifted.push(drift.to_string())
+//! })
+//! .unwrap();
+//!
+//! assert_eq!(drifted.len(), 1);
+//! assert!(drifted[0].ends_with("ApiError ignored `.code`"));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::classify::Branch;
+use crate::error::DecodeError;
+use crate::repr::ResultLike;
+use crate::repr::untagged::{self, AmbiguityPolicy, PreferOk};
+use crate::tape::Tape;
+
+/// A decoded `JsonResult` together with the paths of the keys its branch ignored.
+///
+/// Decodes like the wrapped type, resolving payloads that parse as both branches with the
+/// policy `P` like [`Untagged`](crate::repr::Untagged), and serializes exactly like it.
+#[derive(Debug)]
+pub struct Reported<J, P = PreferOk> {
+    value: J,
+    ignored: Vec<String>,
+    marker: PhantomData<P>,
+}
+
+impl<J, P> Reported<J, P> {
+    /// The decoded value.
+    pub fn value(&self) -> &J {
+        &self.value
+    }
+
+    /// Paths of the keys the chosen branch ignored, like `.meta.region` or `.items[0].note`,
+    /// in the order they appeared in the payload.
+    pub fn ignored(&self) -> &[String] {
+        &self.ignored
+    }
+
+    /// Whether the chosen branch used every key in the payload.
+    pub fn is_exact(&self) -> bool {
+        self.ignored.is_empty()
+    }
+
+    /// Returns the decoded value.
+    pub fn into_inner(self) -> J {
+        self.value
+    }
+
+    /// Returns the decoded value and the ignored paths.
+    pub fn into_parts(self) -> (J, Vec<String>) {
+        (self.value, self.ignored)
+    }
+}
+
+impl<J: ResultLike, P: AmbiguityPolicy> Reported<J, P> {
+    /// Parses `s` and decodes it, keeping the failure typed like
+    /// [`Untagged::from_str`](crate::repr::Untagged::from_str).
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str<'de>(s: &'de str) -> Result<Self, DecodeError>
+    where
+        J::Ok: Deserialize<'de>,
+        J::Err: Deserialize<'de>,
+    {
+        Reported::from_str_with(s, |_| {})
+    }
+
+    /// Like [`Reported::from_str`], for bytes.
+    pub fn from_slice<'de>(v: &'de [u8]) -> Result<Self, DecodeError>
+    where
+        J::Ok: Deserialize<'de>,
+        J::Err: Deserialize<'de>,
+    {
+        Reported::from_slice_with(v, |_| {})
+    }
+
+    /// Decodes `value`; both branches may borrow from it.
+    pub fn from_value<'de>(value: &'de Value) -> Result<Self, DecodeError>
+    where
+        J::Ok: Deserialize<'de>,
+        J::Err: Deserialize<'de>,
+    {
+        Reported::from_value_with(value, |_| {})
+    }
+
+    /// Like [`Reported::from_str`], and calls `on_drift` if the chosen branch ignored keys.
+    pub fn from_str_with<'de, F>(s: &'de str, on_drift: F) -> Result<Self, DecodeError>
+    where
+        J::Ok: Deserialize<'de>,
+        J::Err: Deserialize<'de>,
+        F: FnOnce(&Drift<'_>),
+    {
+        Reported::from_slice_with(s.as_bytes(), on_drift)
+    }
+
+    /// Like [`Reported::from_slice`], and calls `on_drift` if the chosen branch ignored keys.
+    pub fn from_slice_with<'de, F>(v: &'de [u8], on_drift: F) -> Result<Self, DecodeError>
+    where
+        J::Ok: Deserialize<'de>,
+        J::Err: Deserialize<'de>,
+        F: FnOnce(&Drift<'_>),
+    {
+        Reported::decode(untagged::record(v).map_err(DecodeError::Json)?, on_drift)
+    }
+
+    /// Like [`Reported::from_value`], and calls `on_drift` if the chosen branch ignored keys.
+    pub fn from_value_with<'de, F>(value: &'de Value, on_drift: F) -> Result<Self, DecodeError>
+    where
+        J::Ok: Deserialize<'de>,
+        J::Err: Deserialize<'de>,
+        F: FnOnce(&Drift<'_>),
+    {
+        Reported::decode(value, on_drift)
+    }
+
+    fn decode<'de, B, F>(input: B, on_drift: F) -> Result<Self, DecodeError>
+    where
+        J::Ok: Deserialize<'de>,
+        J::Err: Deserialize<'de>,
+        B: untagged::Buffered<'de>,
+        F: FnOnce(&Drift<'_>),
+    {
+        let (result, ignored) = untagged::decode_tracked(input, P::ON_AMBIGUITY, true, |_| Ok(()))?;
+        if !ignored.is_empty() {
+            let drift = match &result {
+                Ok(_) => Drift::new::<J::Ok>(Branch::Ok, &ignored),
+                Err(_) => Drift::new::<J::Err>(Branch::Err, &ignored),
+            };
+            trace(&drift);
+            on_drift(&drift);
+        }
+        Ok(Reported {
+            value: J::from_result(result),
+            ignored,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<'de, J, P> Deserialize<'de> for Reported<J, P>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    P: AmbiguityPolicy,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let tape = Tape::deserialize(deserializer)?;
+        Reported::decode(tape, |_| {}).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<J: Serialize, P> Serialize for Reported<J, P> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+impl<J, P> Deref for Reported<J, P> {
+    type Target = J;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// A decode whose chosen branch ignored some of the payload's keys, as passed to the callback
+/// and the `tracing` event.
+#[derive(Debug, Clone, Copy)]
+pub struct Drift<'a> {
+    branch: Branch,
+    type_name: &'static str,
+    ignored: &'a [String],
+}
+
+impl<'a> Drift<'a> {
+    fn new<V>(branch: Branch, ignored: &'a [String]) -> Self {
+        Drift {
+            branch,
+            type_name: core::any::type_name::<V>(),
+            ignored,
+        }
+    }
+
+    /// The branch that was decoded, [`Branch::Ok`] or [`Branch::Err`].
+    pub fn branch(&self) -> Branch {
+        self.branch
+    }
+
+    /// The name of the type the payload was decoded as.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Paths of the ignored keys.
+    pub fn ignored(&self) -> &'a [String] {
+        self.ignored
+    }
+}
+
+impl fmt::Display for Drift<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ignored ", self.type_name)?;
+        for (i, path) in self.ignored.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "`{}`", path)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn trace(drift: &Drift<'_>) {
+    tracing::warn!(
+        target: "json_result::drift",
+        branch = ?drift.branch,
+        type_name = drift.type_name,
+        ignored = ?drift.ignored,
+        "decoded payload has keys the chosen type ignored"
+    );
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace(_: &Drift<'_>) {}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::Reported;
+    use crate::classify::Branch;
+    use crate::error::DecodeError;
+    use crate::repr::{PreferErr, Strict};
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Item {
+        name: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Order {
+        id: u32,
+        items: Vec<Item>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct ApiError {
+        error: String,
+    }
+
+    #[test]
+    fn test_reports_nested_paths() {
+        let body = r#"{
+            "id": 1,
+            "channel": "web",
+            "items": [{"name": "a"}, {"name": "b", "colour": "red"}]
+        }"#;
+        let reported = Reported::<r#struct::JsonResult<Order, ApiError>>::from_str(body).unwrap();
+
+        assert_eq!(reported.ignored(), [".channel", ".items[1].colour"]);
+        assert!(!reported.is_exact());
+        assert_eq!(reported.value().as_ref().unwrap().items.len(), 2);
+    }
+
+    #[test]
+    fn test_exact_payload() {
+        let value = json!({ "id": 2, "items": [] });
+        let reported = Reported::<r#enum::JsonResult<Order, ApiError>>::from_value(&value).unwrap();
+
+        assert!(reported.is_exact());
+        assert!(matches!(
+            reported.into_inner(),
+            r#enum::JsonResult::Ok(Order { id: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_reports_err_branch_inside_derive() {
+        #[derive(Debug, Deserialize)]
+        struct Response {
+            status: u16,
+            body: Reported<r#enum::JsonResult<Order, ApiError>>,
+        }
+
+        let input = r#"{"status": 500, "body": {"error": "boom", "request_id": "r-1"}}"#;
+        let response: Response = serde_json::from_str(input).unwrap();
+
+        assert_eq!(response.status, 500);
+        let (body, ignored) = response.body.into_parts();
+        assert!(matches!(body, r#enum::JsonResult::Err(ApiError { .. })));
+        assert_eq!(ignored, [".request_id"]);
+    }
+
+    #[test]
+    fn test_serializes_like_inner() {
+        let reported = Reported::<r#struct::JsonResult<Item, ApiError>>::from_slice(
+            br#"{"name": "x", "legacy": 1}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&reported).unwrap(),
+            json!({ "name": "x" })
+        );
+    }
+
+    #[test]
+    fn test_failure_is_typed() {
+        let err = Reported::<r#struct::JsonResult<Order, ApiError>>::from_str("[1]").unwrap_err();
+        assert!(matches!(err, DecodeError::NoMatch(_)));
+    }
+
+    #[test]
+    fn test_callback_sees_drift() {
+        let mut seen = Vec::new();
+        Reported::<r#struct::JsonResult<Order, ApiError>>::from_str_with(
+            r#"{"id": 1, "items": []}"#,
+            |drift| seen.push(drift.to_string()),
+        )
+        .unwrap();
+        Reported::<r#struct::JsonResult<Order, ApiError>>::from_str_with(
+            r#"{"id": 1, "items": [], "v": 2}"#,
+            |drift| {
+                assert_eq!(drift.branch(), Branch::Ok);
+                seen.push(drift.to_string())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(seen.len(), 1);
+        assert!(seen[0].ends_with("Order ignored `.v`"));
+    }
+
+    #[test]
+    fn test_ambiguity_policy() {
+        #[derive(Debug, Deserialize)]
+        struct Loose {}
+
+        type Both = r#struct::JsonResult<Loose, Loose>;
+        let body = r#"{"x": 1}"#;
+
+        let reported = Reported::<Both, PreferErr>::from_str(body).unwrap();
+        assert!(reported.value().is_err());
+        assert_eq!(reported.ignored(), [".x"]);
+        assert!(matches!(
+            Reported::<Both, Strict>::from_str(body),
+            Err(DecodeError::Ambiguous(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_paths_report() {
+        let body = r#"{"error": "boom", "trace": "t-1"}"#;
+
+        let reported = r#struct::JsonResult::<Order, ApiError>::from_str_reported(body).unwrap();
+        assert_eq!(reported.ignored(), [".trace"]);
+
+        let value: serde_json::Value = serde_json::from_str(body).unwrap();
+        let reported = r#enum::JsonResult::<Order, ApiError>::from_value_reported(&value).unwrap();
+        assert_eq!(reported.ignored(), [".trace"]);
+        assert!(matches!(
+            reported.into_inner(),
+            r#enum::JsonResult::Err(ApiError { .. })
+        ));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_event() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        struct Counter(Arc<AtomicUsize>);
+
+        impl Subscriber for Counter {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                if event.metadata().target() == "json_result::drift" {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let events = Arc::new(AtomicUsize::new(0));
+        tracing::subscriber::with_default(Counter(events.clone()), || {
+            Reported::<r#struct::JsonResult<Item, ApiError>>::from_str(r#"{"name": "a"}"#).unwrap();
+            Reported::<r#struct::JsonResult<Item, ApiError>>::from_str(r#"{"name": "a", "b": 1}"#)
+                .unwrap();
+        });
+
+        assert_eq!(events.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,4 +1,12 @@
-use serde::de::{DeserializeOwned, Error};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::drift::Reported;
+use crate::error::DecodeError;
+
+use crate::repr::ResultLike;
+use crate::repr::untagged::{self, Ambiguity};
 
 /// A generic enum representing a JSON result that can either be a success (`Ok`) with a value of type `T`
 /// or an error (`Err`) with a value of type `E`.
@@ -14,6 +22,28 @@ pub enum JsonResult<T, E> {
     Err(E),
 }
 
+impl<T, E> JsonResult<T, E> {
+    /// Parses `s` like deserializing a `JsonResult` does, and also returns the paths of the
+    /// keys the chosen branch ignored; see [`drift`](crate::drift).
+    pub fn from_str_reported<'de>(s: &'de str) -> Result<Reported<Self>, DecodeError>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+    {
+        Reported::from_str(s)
+    }
+
+    /// Like [`JsonResult::from_str_reported`], for a `serde_json::Value` both branches may
+    /// borrow from.
+    pub fn from_value_reported<'de>(value: &'de Value) -> Result<Reported<Self>, DecodeError>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+    {
+        Reported::from_value(value)
+    }
+}
+
 impl<T, E> From<JsonResult<T, E>> for serde_json::Value
 where
     T: serde::Serialize,
@@ -69,22 +99,33 @@ where
     /// }
     /// ```
     fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
-        let t_res = serde_json::from_value::<T>(value.clone());
-        let e_res = serde_json::from_value::<E>(value);
-
-        match (t_res, e_res) {
-            (Ok(v), _) => Ok(JsonResult::Ok(v)),
-            (_, Ok(e)) => Ok(JsonResult::Err(e)),
-            (Err(t_err), Err(e_err)) => {
-                let t_name = std::any::type_name::<T>();
-                let e_name = std::any::type_name::<E>();
-                let message = format!(
-                    "Failed to parse as {}: {}\nFailed to parse as {}: {}",
-                    t_name, t_err, e_name, e_err
-                );
-                Err(serde_json::Error::custom(message))
-            }
-        }
+        JsonResult::try_from(&value)
+    }
+}
+
+impl<'a, T, E> TryFrom<&'a serde_json::Value> for JsonResult<T, E>
+where
+    T: Deserialize<'a>,
+    E: Deserialize<'a>,
+{
+    type Error = serde_json::Error;
+
+    /// Like the conversion from an owned `serde_json::Value`, but `T` and `E` may borrow from
+    /// `value`, e.g. `&'a str` or `#[serde(borrow)]` fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use serde_json::json;
+    /// # use json_result::r#enum::JsonResult;
+    /// let json_val = json!("borrowed");
+    /// let res = JsonResult::<&str, u32>::try_from(&json_val).unwrap();
+    /// assert!(matches!(res, JsonResult::Ok("borrowed")));
+    /// ```
+    fn try_from(value: &'a serde_json::Value) -> Result<Self, Self::Error> {
+        untagged::decode(value, Ambiguity::PreferOk)
+            .map(JsonResult::from)
+            .map_err(serde_json::Error::from)
     }
 }
 
@@ -97,6 +138,29 @@ impl<T, E> From<Result<T, E>> for JsonResult<T, E> {
     }
 }
 
+impl<T, E> ResultLike for JsonResult<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn from_result(result: Result<T, E>) -> Self {
+        result.into()
+    }
+
+    fn as_result(&self) -> Result<&T, &E> {
+        match self {
+            JsonResult::Ok(v) => Ok(v),
+            JsonResult::Err(e) => Err(e),
+        }
+    }
+
+    fn into_result(self) -> Result<T, E> {
+        match self {
+            JsonResult::Ok(v) => Ok(v),
+            JsonResult::Err(e) => Err(e),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::r#enum::JsonResult;
@@ -284,6 +348,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_deeply_nested_failure_reports_path() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct NestedT {
+            nested: Option<Box<NestedT>>,
+            val: u32,
+        }
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct NestedE {
+            error: String,
+        }
+
+        let json = serde_json::json!({
+            "nested": {
+                "nested": { "nested": null, "val": "ten" },
+                "val": 10
+            },
+            "val": 5
+        });
+
+        let msg = JsonResult::<NestedT, NestedE>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("(at .nested.nested.val)"));
+    }
+
     #[test]
     fn test_invalid_json_structure() {
         // JSON array will not deserialize to GoodT or BadE structs
@@ -363,4 +455,26 @@ mod tests {
 
         assert!(matches!(jr, JsonResult::Err("wrong")));
     }
+
+    #[test]
+    fn test_try_from_borrowed_value() {
+        #[derive(Debug, Deserialize)]
+        struct Record<'a> {
+            level: &'a str,
+        }
+
+        let json = serde_json::json!({ "level": "warn" });
+        let jr = JsonResult::<Record<'_>, u32>::try_from(&json).unwrap();
+
+        match jr {
+            JsonResult::Ok(record) => {
+                assert_eq!(record.level, "warn");
+                assert_eq!(
+                    record.level.as_ptr(),
+                    json["level"].as_str().unwrap().as_ptr()
+                );
+            }
+            JsonResult::Err(_) => panic!("Expected Ok variant"),
+        }
+    }
 }
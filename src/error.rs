@@ -0,0 +1,508 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+
+use serde_json::Value;
+use serde_json::error::Category;
+use serde_path_to_error::Segment;
+
+use crate::classify::Branch;
+use crate::diagnose::Diagnosis;
+use crate::validate::Violations;
+
+/// Error returned when decoding a `JsonResult` fails.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DecodeError {
+    /// The input was not valid JSON, so neither branch was tried.
+    Json(serde_json::Error),
+    /// The payload parsed as neither `T` nor `E`.
+    NoMatch(JsonResultError),
+    /// The payload parsed as both `T` and `E` and the policy rejects ambiguity.
+    Ambiguous(AmbiguousPayload),
+    /// The payload did not parse as the branch it was classified as.
+    Mismatch(serde_json::Error),
+    /// The classifier refused to pick a branch for the payload.
+    Unclassified(String),
+    /// simd-json rejected the input, so neither branch was tried.
+    #[cfg(feature = "simd-json")]
+    SimdJson(simd_json::Error),
+    /// sonic-rs rejected the input, so neither branch was tried.
+    #[cfg(feature = "sonic-rs")]
+    SonicRs(sonic_rs::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Json(e) => e.fmt(f),
+            DecodeError::NoMatch(e) => e.fmt(f),
+            DecodeError::Ambiguous(e) => e.fmt(f),
+            DecodeError::Mismatch(e) => e.fmt(f),
+            DecodeError::Unclassified(msg) => write!(f, "Unclassified payload: {}", msg),
+            #[cfg(feature = "simd-json")]
+            DecodeError::SimdJson(e) => e.fmt(f),
+            #[cfg(feature = "sonic-rs")]
+            DecodeError::SonicRs(e) => e.fmt(f),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            DecodeError::Json(e) => Some(e),
+            DecodeError::NoMatch(e) => Some(e),
+            DecodeError::Ambiguous(e) => Some(e),
+            DecodeError::Mismatch(e) => Some(e),
+            DecodeError::Unclassified(_) => None,
+            #[cfg(feature = "simd-json")]
+            DecodeError::SimdJson(e) => Some(e),
+            #[cfg(feature = "sonic-rs")]
+            DecodeError::SonicRs(e) => Some(e),
+        }
+    }
+}
+
+impl From<DecodeError> for serde_json::Error {
+    fn from(e: DecodeError) -> Self {
+        match e {
+            DecodeError::Json(e) | DecodeError::Mismatch(e) => e,
+            DecodeError::NoMatch(e) => e.into(),
+            other => serde::de::Error::custom(other),
+        }
+    }
+}
+
+/// JsonResultError
+///
+/// A payload that parsed as neither `T` nor `E`, keeping the error from each branch.
+///
+/// Its `Display` output is the combined message used throughout the crate:
+///
+/// ```text
+/// Failed to parse as <T>: <error>
+/// Failed to parse as <E>: <error>
+/// ```
+///
+/// with ` (at <path>)` appended to a line when that branch failed below the root. When one
+/// branch got further than the other, a third line names it along with the fields it lacked or
+/// did not expect:
+///
+/// ```text
+/// Closest match: <T>: missing `email`; unexpected `emial` (did you mean `email`?)
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// use json_result::error::DecodeError;
+/// use json_result::repr::{PreferOk, Untagged};
+///
+/// let err = Untagged::<PreferOk>::from_str::<u32, bool>(r#""text""#).unwrap_err();
+/// let DecodeError::NoMatch(err) = err else { panic!("expected NoMatch") };
+///
+/// assert_eq!(err.ok().type_name(), "u32");
+/// assert!(err.ok().error().is_data());
+/// assert_eq!(err.err().type_name(), "bool");
+/// assert_eq!(err.err().path(), ".");
+/// ```
+#[derive(Debug)]
+pub struct JsonResultError {
+    ok: BranchError,
+    err: BranchError,
+}
+
+impl JsonResultError {
+    pub(crate) fn new(ok: BranchError, err: BranchError) -> Self {
+        JsonResultError { ok, err }
+    }
+
+    /// Why the payload did not parse as `T`.
+    pub fn ok(&self) -> &BranchError {
+        &self.ok
+    }
+
+    /// Why the payload did not parse as `E`.
+    pub fn err(&self) -> &BranchError {
+        &self.err
+    }
+
+    /// Consumes the error and returns the `T` and `E` branch errors.
+    pub fn into_branches(self) -> (BranchError, BranchError) {
+        (self.ok, self.err)
+    }
+
+    /// The broken invariants of the `T` branch, when it parsed but failed validation; see
+    /// [`BranchError::violations`].
+    pub fn violations(&self) -> Option<&Violations> {
+        self.ok.violations()
+    }
+
+    /// The branch that came closer to parsing, or [`Branch::Either`] on a tie.
+    ///
+    /// A branch that failed deeper in the payload is closer; after that, the one that
+    /// recognised more keys, then the one with fewer missing and unexpected fields.
+    pub fn closest(&self) -> Branch {
+        let ok = self.ok.diagnosis().closeness();
+        let err = self.err.diagnosis().closeness();
+        match ok.cmp(&err) {
+            core::cmp::Ordering::Greater => Branch::Ok,
+            core::cmp::Ordering::Less => Branch::Err,
+            core::cmp::Ordering::Equal => Branch::Either,
+        }
+    }
+}
+
+impl fmt::Display for JsonResultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n{}", self.ok, self.err)?;
+        let closest = match self.closest() {
+            Branch::Ok => &self.ok,
+            Branch::Err => &self.err,
+            Branch::Either => return Ok(()),
+        };
+        write!(f, "\nClosest match: {}", closest.type_name)?;
+        if !closest.diagnosis().is_empty() {
+            write!(f, ": {}", closest.diagnosis())?;
+        }
+        Ok(())
+    }
+}
+
+/// Has no single source: neither branch error caused the other, so both are part of the
+/// message and reachable through [`JsonResultError::ok`] and [`JsonResultError::err`].
+impl core::error::Error for JsonResultError {}
+
+impl From<JsonResultError> for serde_json::Error {
+    fn from(e: JsonResultError) -> Self {
+        serde::de::Error::custom(e)
+    }
+}
+
+/// Renders a `serde_path_to_error` path as `.items[3].price`, or `.` for the root.
+pub(crate) fn render_path<'a>(segments: impl IntoIterator<Item = &'a Segment>) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Seq { index } => path.push_str(&format!("[{}]", index)),
+            Segment::Map { key } | Segment::Enum { variant: key } => {
+                path.push('.');
+                path.push_str(key);
+            }
+            Segment::Unknown => path.push_str(".?"),
+        }
+    }
+    if path.is_empty() {
+        path.push('.');
+    }
+    path
+}
+
+/// Why a payload did not parse as one branch of a `JsonResult`.
+#[derive(Debug)]
+pub struct BranchError {
+    type_name: &'static str,
+    path: String,
+    error: serde_json::Error,
+    /// Boxed to keep `Result<_, DecodeError>` small.
+    details: Box<Details>,
+}
+
+/// What a [`BranchError`] found out on top of the error itself.
+#[derive(Debug, Default)]
+struct Details {
+    diagnosis: Diagnosis,
+    violations: Option<Violations>,
+}
+
+impl BranchError {
+    pub(crate) fn new<V>(error: serde_json::Error, path: String) -> Self {
+        BranchError {
+            type_name: core::any::type_name::<V>(),
+            path,
+            error,
+            details: Box::default(),
+        }
+    }
+
+    pub(crate) fn with_diagnosis(mut self, diagnosis: Diagnosis) -> Self {
+        self.details.diagnosis = diagnosis;
+        self
+    }
+
+    pub(crate) fn with_violations(mut self, violations: Violations) -> Self {
+        self.details.violations = Some(violations);
+        self
+    }
+
+    pub(crate) fn located<V>(error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let path = render_path(error.path().iter());
+        BranchError::new::<V>(error.into_inner(), path)
+    }
+
+    /// Type name of the branch, e.g. `my_crate::User`.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Where in the payload the branch failed, e.g. `.items[3].price`, or `.` for the root.
+    ///
+    /// For a missing field this is the object that lacks it.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The underlying `serde_json` error.
+    pub fn error(&self) -> &serde_json::Error {
+        &self.error
+    }
+
+    /// Consumes the error and returns the underlying `serde_json` error.
+    pub fn into_error(self) -> serde_json::Error {
+        self.error
+    }
+
+    /// Line of the failure in the JSON text, starting at 1.
+    ///
+    /// Known when the text itself was decoded, e.g. by [`Untagged::from_str`] or
+    /// [`Untagged::from_slice`], and relative to the record or element for the streaming
+    /// readers. It is 0 when the branch was decoded from a `Value` or through another
+    /// deserializer, such as `serde_json::from_str::<JsonResult<T, E>>`, where no text is kept.
+    ///
+    /// [`Untagged::from_str`]: crate::repr::Untagged::from_str
+    /// [`Untagged::from_slice`]: crate::repr::Untagged::from_slice
+    pub fn line(&self) -> usize {
+        self.error.line()
+    }
+
+    /// Column of the failure in the JSON text, starting at 1; 0 when [`BranchError::line`] is.
+    pub fn column(&self) -> usize {
+        self.error.column()
+    }
+
+    /// Category of the failure, e.g. [`Category::Data`] for a shape mismatch.
+    pub fn category(&self) -> Category {
+        self.error.classify()
+    }
+
+    /// Missing and unexpected fields at the failure, and how far the branch got.
+    pub fn diagnosis(&self) -> &Diagnosis {
+        &self.details.diagnosis
+    }
+
+    /// The broken invariants, when the branch parsed but failed validation. Only the decode
+    /// functions of [`validate`](crate::validate) check the `T` branch this way.
+    pub fn violations(&self) -> Option<&Violations> {
+        self.details.violations.as_ref()
+    }
+}
+
+impl fmt::Display for BranchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to parse as {}: {}", self.type_name, self.error)?;
+        if self.path != "." {
+            write!(f, " (at {})", self.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for BranchError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// A payload that parsed as both `T` and `E` under a policy that does not allow it.
+#[derive(Debug)]
+pub struct AmbiguousPayload {
+    ok_type: &'static str,
+    err_type: &'static str,
+    payload: Value,
+}
+
+impl AmbiguousPayload {
+    pub(crate) fn new<T, E>(payload: Value) -> Self {
+        AmbiguousPayload {
+            ok_type: core::any::type_name::<T>(),
+            err_type: core::any::type_name::<E>(),
+            payload,
+        }
+    }
+
+    /// Type name of the `Ok` branch.
+    pub fn ok_type(&self) -> &'static str {
+        self.ok_type
+    }
+
+    /// Type name of the `Err` branch.
+    pub fn err_type(&self) -> &'static str {
+        self.err_type
+    }
+
+    /// The payload that matched both branches.
+    pub fn payload(&self) -> &Value {
+        &self.payload
+    }
+
+    /// Consumes the error and returns the payload.
+    pub fn into_payload(self) -> Value {
+        self.payload
+    }
+}
+
+impl fmt::Display for AmbiguousPayload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Ambiguous payload: parses as both {} and {}: {}",
+            self.ok_type, self.err_type, self.payload
+        )
+    }
+}
+
+impl core::error::Error for AmbiguousPayload {}
+
+/// A record in a line-delimited stream that failed to decode.
+///
+/// The stream carries on with the next line; see `ndjson::JsonResultStream`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct LineError {
+    line: usize,
+    error: DecodeError,
+}
+
+#[cfg(feature = "std")]
+impl LineError {
+    pub(crate) fn new(line: usize, error: DecodeError) -> Self {
+        LineError { line, error }
+    }
+
+    /// 1-based line number of the record.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Why the record did not decode.
+    pub fn error(&self) -> &DecodeError {
+        &self.error
+    }
+
+    /// Consumes the error and returns the decode error.
+    pub fn into_error(self) -> DecodeError {
+        self.error
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for LineError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An element of a streamed JSON array that failed to decode.
+///
+/// The stream carries on with the next element; see `array::ArrayStream`.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct ElementError {
+    index: usize,
+    error: DecodeError,
+}
+
+#[cfg(feature = "std")]
+impl ElementError {
+    pub(crate) fn new(index: usize, error: DecodeError) -> Self {
+        ElementError { index, error }
+    }
+
+    /// 0-based index of the element in the array.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Why the element did not decode.
+    pub fn error(&self) -> &DecodeError {
+        &self.error
+    }
+
+    /// Consumes the error and returns the decode error.
+    pub fn into_error(self) -> DecodeError {
+        self.error
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ElementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "element {}: {}", self.index, self.error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for ElementError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// A payload that parsed as neither `T` nor `E`, kept whole alongside both branch errors.
+///
+/// This is what `outcome::Outcome::Unrecognized` holds in place of failing.
+#[derive(Debug)]
+pub struct DecodeFailure {
+    payload: Value,
+    error: Box<JsonResultError>,
+}
+
+impl DecodeFailure {
+    pub(crate) fn new(payload: Value, error: JsonResultError) -> Self {
+        DecodeFailure {
+            payload,
+            error: Box::new(error),
+        }
+    }
+
+    /// The payload as it appeared in the input.
+    pub fn payload(&self) -> &Value {
+        &self.payload
+    }
+
+    /// Why the payload parsed as neither branch.
+    pub fn error(&self) -> &JsonResultError {
+        &self.error
+    }
+
+    /// Consumes the failure and returns the payload.
+    pub fn into_payload(self) -> Value {
+        self.payload
+    }
+
+    /// Consumes the failure and returns the payload and the branch errors.
+    pub fn into_parts(self) -> (Value, JsonResultError) {
+        (self.payload, *self.error)
+    }
+}
+
+impl fmt::Display for DecodeFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl core::error::Error for DecodeFailure {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
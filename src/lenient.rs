@@ -0,0 +1,459 @@
+//! Collections of `JsonResult`s that survive elements fitting neither branch.
+//!
+//! Deserializing `Vec<JsonResult<T, E>>` fails as a whole when a single element is neither a
+//! `T` nor an `E`. [`Lenient`] decodes the same input element by element instead, keeping the
+//! ones that decoded and setting the others aside as [`Rejected`] entries with their index or
+//! key, raw value and error.
+//!
+//! Input that is not valid JSON, or not a sequence or map at all, still fails the whole decode.
+//!
+//! An entry that parses as both branches decodes as `T`. A policy parameter such as
+//! `Lenient<Vec<J>, Strict>` picks another [`Ambiguity`](crate::repr::Ambiguity) rule, and under
+//! `Strict` an ambiguous entry is rejected like one that fits neither branch.
+//!
+//! ```rust
+//! use json_result::lenient::Lenient;
+//! use json_result::r#struct::JsonResult;
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Created {
+//!     id: u64,
+//! }
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Refused {
+//!     reason: String,
+//! }
+//!
+//! let body = r#"[{"id": 1}, {"reason": "duplicate"}, {"id": "two"}, {"id": 3}]"#;
+//! let bulk: Lenient<Vec<JsonResult<Created, Refused>>> = serde_json::from_str(body).unwrap();
+//!
+//! assert_eq!(bulk.len(), 3);
+//! assert_eq!(bulk.rejected().len(), 1);
+//! assert_eq!(*bulk.rejected()[0].key(), 2);
+//! assert_eq!(bulk.rejected()[0].raw(), &serde_json::json!({"id": "two"}));
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::DecodeError;
+use crate::repr::untagged;
+use crate::repr::{AmbiguityPolicy, PreferOk, ResultLike};
+use crate::tape::Tape;
+
+/// A collection of `JsonResult`s that [`Lenient`] can decode, and how it names an entry.
+pub trait Collection {
+    /// What identifies an entry: the index for sequences, the key for maps.
+    type Key;
+}
+
+impl<J: ResultLike> Collection for Vec<J> {
+    type Key = usize;
+}
+
+impl<K, J: ResultLike> Collection for BTreeMap<K, J> {
+    type Key = K;
+}
+
+#[cfg(feature = "std")]
+impl<K, J: ResultLike, S> Collection for HashMap<K, J, S> {
+    type Key = K;
+}
+
+/// Lenient<C, P>
+///
+/// Decodes the collection `C` of `JsonResult`s (a `Vec`, `BTreeMap` or `HashMap`) while setting
+/// aside the entries that fit neither branch, instead of failing on the first one. `P` picks
+/// which branch an entry that fits both decodes as, as for [`Untagged`](crate::repr::Untagged).
+///
+/// Entries that decoded are reachable through `Deref` or [`Lenient::into_inner`]; the others
+/// are listed by [`Lenient::rejected`] in input order. Serializing writes only the decoded
+/// entries.
+#[derive(Debug)]
+pub struct Lenient<C: Collection, P = PreferOk> {
+    value: C,
+    rejected: Vec<Rejected<C::Key>>,
+    marker: PhantomData<P>,
+}
+
+impl<C: Collection, P> Lenient<C, P> {
+    fn new(value: C, rejected: Vec<Rejected<C::Key>>) -> Self {
+        Lenient {
+            value,
+            rejected,
+            marker: PhantomData,
+        }
+    }
+
+    /// Entries that fit neither branch, in input order.
+    pub fn rejected(&self) -> &[Rejected<C::Key>] {
+        &self.rejected
+    }
+
+    /// Whether every entry decoded.
+    pub fn is_complete(&self) -> bool {
+        self.rejected.is_empty()
+    }
+
+    /// Returns the decoded entries.
+    pub fn into_inner(self) -> C {
+        self.value
+    }
+
+    /// Returns the decoded entries and the rejected ones.
+    pub fn into_parts(self) -> (C, Vec<Rejected<C::Key>>) {
+        (self.value, self.rejected)
+    }
+}
+
+impl<C: Collection, P> Deref for Lenient<C, P> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<C: Collection, P> DerefMut for Lenient<C, P> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<C: Collection + Serialize, P> Serialize for Lenient<C, P> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.value.serialize(serializer)
+    }
+}
+
+/// An entry of a [`Lenient`] collection that parsed as neither `T` nor `E`.
+#[derive(Debug)]
+pub struct Rejected<K> {
+    key: K,
+    raw: Value,
+    error: DecodeError,
+}
+
+impl<K> Rejected<K> {
+    /// Index or key of the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// The entry as it appeared in the input.
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+
+    /// Why the entry did not decode.
+    pub fn error(&self) -> &DecodeError {
+        &self.error
+    }
+
+    /// Consumes the entry and returns its key, raw value and error.
+    pub fn into_parts(self) -> (K, Value, DecodeError) {
+        (self.key, self.raw, self.error)
+    }
+}
+
+impl<K: fmt::Debug> fmt::Display for Rejected<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entry {:?}: {}", self.key, self.error)
+    }
+}
+
+impl<K: fmt::Debug> core::error::Error for Rejected<K> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Decodes one recorded entry, or sets it aside in `rejected` when it does not decode under `P`.
+fn entry<'de, J, K, P>(key: K, tape: Tape<'de>, rejected: &mut Vec<Rejected<K>>) -> Option<(K, J)>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    P: AmbiguityPolicy,
+{
+    match untagged::decode(&tape, P::ON_AMBIGUITY) {
+        Ok(result) => Some((key, J::from_result(result))),
+        Err(error) => {
+            rejected.push(Rejected {
+                key,
+                raw: tape.to_value(),
+                error,
+            });
+            None
+        }
+    }
+}
+
+impl<'de, J, P> Deserialize<'de> for Lenient<Vec<J>, P>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    P: AmbiguityPolicy,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct SeqVisitor<J, P>(PhantomData<(J, P)>);
+
+        impl<'de, J, P> Visitor<'de> for SeqVisitor<J, P>
+        where
+            J: ResultLike,
+            J::Ok: Deserialize<'de>,
+            J::Err: Deserialize<'de>,
+            P: AmbiguityPolicy,
+        {
+            type Value = Lenient<Vec<J>, P>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let mut value = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+                let mut rejected = Vec::new();
+                let mut index = 0;
+                while let Some(tape) = seq.next_element::<Tape<'de>>()? {
+                    if let Some((_, item)) = entry::<_, _, P>(index, tape, &mut rejected) {
+                        value.push(item);
+                    }
+                    index += 1;
+                }
+                Ok(Lenient::new(value, rejected))
+            }
+        }
+
+        deserializer.deserialize_seq(SeqVisitor(PhantomData))
+    }
+}
+
+/// Fills any map type entry by entry, setting aside values that fit neither branch.
+struct MapVisitor<M, K, J, P>(PhantomData<(M, K, J, P)>);
+
+impl<'de, M, K, J, P> Visitor<'de> for MapVisitor<M, K, J, P>
+where
+    M: Collection<Key = K> + Default + Extend<(K, J)>,
+    K: Deserialize<'de>,
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    P: AmbiguityPolicy,
+{
+    type Value = Lenient<M, P>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut value = M::default();
+        let mut rejected = Vec::new();
+        while let Some((key, tape)) = map.next_entry::<K, Tape<'de>>()? {
+            value.extend(entry::<_, _, P>(key, tape, &mut rejected));
+        }
+        Ok(Lenient::new(value, rejected))
+    }
+}
+
+impl<'de, K, J, P> Deserialize<'de> for Lenient<BTreeMap<K, J>, P>
+where
+    K: Deserialize<'de> + Ord,
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    P: AmbiguityPolicy,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de, K, J, S, P> Deserialize<'de> for Lenient<HashMap<K, J, S>, P>
+where
+    K: Deserialize<'de> + Eq + Hash,
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    S: BuildHasher + Default,
+    P: AmbiguityPolicy,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::Lenient;
+    use crate::error::DecodeError;
+    use crate::repr::test_support::Amb;
+    use crate::repr::{PreferErr, Strict};
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Created {
+        id: u64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Refused {
+        reason: String,
+    }
+
+    type Item = r#struct::JsonResult<Created, Refused>;
+
+    #[test]
+    fn test_keeps_every_good_element() {
+        let mut items: Vec<_> = (0..10_000).map(|id| json!({ "id": id })).collect();
+        items[1234] = json!({ "id": -1 });
+        items[5000] = json!({ "reason": "duplicate" });
+        let body = serde_json::to_string(&items).unwrap();
+
+        let bulk: Lenient<Vec<Item>> = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(bulk.len(), 9_999);
+        assert_eq!(bulk.iter().filter(|jr| jr.is_err()).count(), 1);
+        assert!(!bulk.is_complete());
+
+        let rejected = &bulk.rejected()[0];
+        assert_eq!(bulk.rejected().len(), 1);
+        assert_eq!(*rejected.key(), 1234);
+        assert_eq!(rejected.raw(), &json!({ "id": -1 }));
+        assert!(matches!(rejected.error(), DecodeError::NoMatch(_)));
+        assert!(
+            rejected
+                .to_string()
+                .starts_with("entry 1234: Failed to parse as")
+        );
+    }
+
+    #[test]
+    fn test_enum_flavour_and_order() {
+        let body = r#"[{"reason": "a"}, 7, {"id": 1}, null]"#;
+        let bulk: Lenient<Vec<r#enum::JsonResult<Created, Refused>>> =
+            serde_json::from_str(body).unwrap();
+
+        assert!(matches!(bulk[0], r#enum::JsonResult::Err(_)));
+        assert!(matches!(bulk[1], r#enum::JsonResult::Ok(Created { id: 1 })));
+
+        let (items, rejected) = bulk.into_parts();
+        assert_eq!(items.len(), 2);
+        let keys: Vec<_> = rejected.iter().map(|r| *r.key()).collect();
+        assert_eq!(keys, vec![1, 3]);
+        assert_eq!(rejected[1].raw(), &json!(null));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_maps_record_keys() {
+        use std::collections::HashMap;
+
+        let body = r#"{"a": {"id": 1}, "b": {"id": "x"}, "c": {"reason": "no"}}"#;
+
+        let by_name: Lenient<HashMap<String, Item>> = serde_json::from_str(body).unwrap();
+        assert_eq!(by_name.len(), 2);
+        assert_eq!(by_name["a"].0, Ok(Created { id: 1 }));
+        assert_eq!(by_name.rejected()[0].key(), "b");
+
+        let sorted: Lenient<BTreeMap<String, Item>> = serde_json::from_str(body).unwrap();
+        let keys: Vec<_> = sorted.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["a", "c"]);
+        assert_eq!(sorted.rejected()[0].raw(), &json!({ "id": "x" }));
+    }
+
+    #[test]
+    fn test_ambiguity_policy() {
+        type Either = r#struct::JsonResult<Amb, Amb>;
+        let body = r#"[{"x": 1}, {"y": 2}]"#;
+
+        let prefer_ok: Lenient<Vec<Either>> = serde_json::from_str(body).unwrap();
+        assert_eq!(prefer_ok[0].0, Ok(Amb { x: 1 }));
+
+        let prefer_err: Lenient<Vec<Either>, PreferErr> = serde_json::from_str(body).unwrap();
+        assert_eq!(prefer_err[0].0, Err(Amb { x: 1 }));
+
+        let strict: Lenient<Vec<Either>, Strict> = serde_json::from_str(body).unwrap();
+        assert!(strict.is_empty());
+        assert!(matches!(
+            strict.rejected()[0].error(),
+            DecodeError::Ambiguous(_)
+        ));
+        assert!(matches!(
+            strict.rejected()[1].error(),
+            DecodeError::NoMatch(_)
+        ));
+    }
+
+    #[test]
+    fn test_nested_field() {
+        #[derive(Debug, Deserialize)]
+        struct Response {
+            status: String,
+            results: Lenient<Vec<Item>>,
+        }
+
+        let body = r#"{"status": "partial", "results": [{"id": 1}, [], {"id": 2}]}"#;
+        let response: Response = serde_json::from_str(body).unwrap();
+
+        assert_eq!(response.status, "partial");
+        assert_eq!(response.results.len(), 2);
+        assert_eq!(*response.results.rejected()[0].key(), 1);
+    }
+
+    #[test]
+    fn test_serializes_decoded_entries() {
+        let bulk: Lenient<Vec<Item>> =
+            serde_json::from_str(r#"[{"id": 1}, true, {"reason": "r"}]"#).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&bulk).unwrap(),
+            json!([{ "id": 1 }, { "reason": "r" }])
+        );
+    }
+
+    #[test]
+    fn test_structural_errors_still_fail() {
+        assert!(serde_json::from_str::<Lenient<Vec<Item>>>(r#"[{"id": 1},"#).is_err());
+        assert!(serde_json::from_str::<Lenient<Vec<Item>>>(r#"{"id": 1}"#).is_err());
+        assert!(serde_json::from_str::<Lenient<BTreeMap<String, Item>>>("[]").is_err());
+    }
+}
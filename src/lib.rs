@@ -1,5 +1,44 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod array;
+#[cfg(feature = "tokio")]
+pub mod r#async;
+#[cfg(feature = "alloc")]
+pub mod classify;
+#[cfg(all(test, any(feature = "simd-json", feature = "sonic-rs")))]
+mod conformance;
+#[cfg(feature = "alloc")]
+pub mod diagnose;
+#[cfg(feature = "alloc")]
+pub mod drift;
+#[cfg(feature = "alloc")]
 pub mod r#enum;
+#[cfg(feature = "alloc")]
+pub mod error;
+#[cfg(feature = "alloc")]
+pub mod lenient;
+#[cfg(feature = "std")]
+pub mod ndjson;
+#[cfg(feature = "alloc")]
+pub mod outcome;
+#[cfg(feature = "alloc")]
+pub mod repr;
+#[cfg(feature = "std")]
+pub mod rules;
+#[cfg(feature = "simd-json")]
+pub mod simd;
+#[cfg(feature = "sonic-rs")]
+pub mod sonic;
 pub mod r#struct;
+#[cfg(feature = "alloc")]
+mod tape;
+#[cfg(feature = "alloc")]
+pub mod validate;
 
 pub use serde;
+#[cfg(feature = "alloc")]
 pub use serde_json;
@@ -0,0 +1,530 @@
+//! Line-delimited JSON (NDJSON) streams of `JsonResult` records.
+//!
+//! Each line holds one record that is either a success or an error. [`JsonResultStream`]
+//! decodes them one at a time, reporting a line that fits neither type as a [`LineError`]
+//! without stopping, and [`JsonResultWriter`] writes records back out one per line. A line
+//! longer than [`JsonResultStream::with_max_line_size`] allows is reported the same way and
+//! skipped without being buffered, so a stray unterminated line cannot exhaust memory.
+//!
+//! ```rust
+//! use json_result::ndjson::{JsonResultStream, JsonResultWriter};
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Entry {
+//!     msg: String,
+//! }
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Failure {
+//!     code: u16,
+//! }
+//!
+//! let mut out = Vec::new();
+//! let mut writer = JsonResultWriter::new(&mut out);
+//! writer
+//!     .write_all([Ok(Entry { msg: "up".into() }), Err(Failure { code: 503 })])
+//!     .unwrap();
+//! assert_eq!(out, b"{\"msg\":\"up\"}\n{\"code\":503}\n");
+//!
+//! let records: Vec<_> = JsonResultStream::<_, Entry, Failure>::new(&out[..]).collect();
+//! assert!(records[0].as_ref().unwrap().is_ok());
+//! assert!(records[1].as_ref().unwrap().is_err());
+//! ```
+
+use std::io::{self, BufRead, Write};
+use std::marker::PhantomData;
+
+use serde::Serialize;
+use serde::de::{DeserializeOwned, Error as _};
+
+use crate::error::{DecodeError, LineError};
+use crate::repr::{PreferOk, ResultLike, Untagged};
+use crate::r#struct::JsonResult;
+
+/// Default for [`JsonResultStream::with_max_line_size`]: 16 MiB.
+pub const DEFAULT_MAX_LINE_SIZE: usize = 16 * 1024 * 1024;
+
+/// What ended a line.
+pub(crate) enum End {
+    Newline,
+    Eof,
+    /// The line grew past the size limit before it ended.
+    TooLarge,
+}
+
+/// Splits input into lines without doing any I/O, for the blocking and the async stream.
+///
+/// Callers hand it whatever their reader has buffered, consume as many bytes as it used, and
+/// turn each [`End`] into an item with [`LineFramer::record`].
+pub(crate) struct LineFramer {
+    buf: Vec<u8>,
+    line: usize,
+    max_line_size: usize,
+    /// The rest of an oversized line still has to be discarded.
+    skipping: bool,
+    done: bool,
+}
+
+impl LineFramer {
+    pub(crate) fn new() -> Self {
+        LineFramer {
+            buf: Vec::new(),
+            line: 0,
+            max_line_size: DEFAULT_MAX_LINE_SIZE,
+            skipping: false,
+            done: false,
+        }
+    }
+
+    pub(crate) fn set_max_line_size(&mut self, bytes: usize) {
+        self.max_line_size = bytes;
+    }
+
+    /// Number of lines framed so far, including blank ones.
+    pub(crate) fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Whether the input or an I/O error ended the stream.
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Takes the next `chunk` of input, empty once the input has ended, and returns how many
+    /// of its bytes were used and what ended the line, if anything did.
+    ///
+    /// Stops with [`End::TooLarge`] rather than buffer more than the size limit, and then
+    /// discards the rest of that line from later chunks. Returns [`End::Eof`] only when the
+    /// input ended with nothing left to frame.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> (usize, Option<End>) {
+        let newline = chunk.iter().position(|&b| b == b'\n');
+        if chunk.is_empty() {
+            self.skipping = false;
+            if self.buf.is_empty() {
+                self.done = true;
+                return (0, Some(End::Eof));
+            }
+            self.line += 1;
+            return (0, Some(End::Newline));
+        }
+        if self.skipping {
+            return match newline {
+                Some(i) => {
+                    self.skipping = false;
+                    (i + 1, None)
+                }
+                None => (chunk.len(), None),
+            };
+        }
+        let n = newline.unwrap_or(chunk.len());
+        if self.buf.len() + n > self.max_line_size {
+            self.line += 1;
+            self.skipping = true;
+            self.buf.clear();
+            return (0, Some(End::TooLarge));
+        }
+        self.buf.extend_from_slice(&chunk[..n]);
+        match newline {
+            Some(_) => {
+                self.line += 1;
+                (n + 1, Some(End::Newline))
+            }
+            None => (n, None),
+        }
+    }
+
+    /// Decodes the line `end` finished, or reports it as too long. Returns `None` for a blank
+    /// line and at the end of input.
+    pub(crate) fn record<T, E>(&mut self, end: End) -> Option<Result<JsonResult<T, E>, DecodeError>>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+    {
+        match end {
+            End::Eof => None,
+            End::TooLarge => Some(Err(DecodeError::Json(serde_json::Error::custom(
+                format_args!(
+                    "line is longer than the limit of {} bytes",
+                    self.max_line_size
+                ),
+            )))),
+            End::Newline => {
+                let record = (!self.buf.iter().all(u8::is_ascii_whitespace))
+                    .then(|| Untagged::<PreferOk>::from_slice::<T, E>(&self.buf).map(JsonResult));
+                self.buf.clear();
+                record
+            }
+        }
+    }
+
+    /// Ends the stream with an I/O error from the reader, returning the line it was reading:
+    /// the oversized line being discarded, or the next one.
+    pub(crate) fn fail(&mut self, error: io::Error) -> (usize, DecodeError) {
+        self.done = true;
+        self.buf.clear();
+        let line = if self.skipping {
+            self.line
+        } else {
+            self.line + 1
+        };
+        (line, DecodeError::Json(serde_json::Error::io(error)))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn buf(&self) -> &Vec<u8> {
+        &self.buf
+    }
+}
+
+/// JsonResultStream<R, T, E>
+///
+/// Reads NDJSON records from `R` and decodes each line as `T`, falling back to `E`.
+///
+/// A line that is not JSON, fits neither type, or is over the size limit yields a
+/// [`LineError`] carrying its line number, and the next call moves on to the following line.
+/// Blank lines are skipped. An I/O error from the reader is yielded once as a
+/// [`DecodeError::Json`] and ends the stream.
+pub struct JsonResultStream<R, T, E> {
+    reader: R,
+    framer: LineFramer,
+    marker: PhantomData<fn() -> (T, E)>,
+}
+
+impl<R, T, E> JsonResultStream<R, T, E>
+where
+    R: BufRead,
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    /// Creates a stream over `reader`.
+    pub fn new(reader: R) -> Self {
+        JsonResultStream {
+            reader,
+            framer: LineFramer::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Sets the longest line, in bytes and without its newline, the stream buffers before
+    /// giving up on it. Defaults to [`DEFAULT_MAX_LINE_SIZE`].
+    ///
+    /// A longer line yields a [`LineError`] with its line number, and the rest of it is
+    /// discarded unread.
+    pub fn with_max_line_size(mut self, bytes: usize) -> Self {
+        self.framer.set_max_line_size(bytes);
+        self
+    }
+
+    /// Number of lines read so far, including blank ones.
+    pub fn line(&self) -> usize {
+        self.framer.line()
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, T, E> Iterator for JsonResultStream<R, T, E>
+where
+    R: BufRead,
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    type Item = Result<JsonResult<T, E>, LineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.framer.is_done() {
+            let chunk = match fill_buf(&mut self.reader) {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    let (line, error) = self.framer.fail(e);
+                    return Some(Err(LineError::new(line, error)));
+                }
+            };
+            let (used, end) = self.framer.feed(chunk);
+            self.reader.consume(used);
+            if let Some(record) = end.and_then(|end| self.framer.record(end)) {
+                let line = self.framer.line();
+                return Some(record.map_err(|e| LineError::new(line, e)));
+            }
+        }
+        None
+    }
+}
+
+/// Like [`BufRead::fill_buf`], retrying reads that were interrupted.
+fn fill_buf<R: BufRead>(reader: &mut R) -> io::Result<&[u8]> {
+    loop {
+        match reader.fill_buf() {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+            Ok(_) => break,
+        }
+    }
+    reader.fill_buf()
+}
+
+/// JsonResultWriter<W>
+///
+/// Writes `JsonResult` or plain `Result` records to `W` as NDJSON, serializing whichever
+/// branch is present on its own line.
+pub struct JsonResultWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonResultWriter<W> {
+    /// Creates a writer over `writer`.
+    pub fn new(writer: W) -> Self {
+        JsonResultWriter { writer }
+    }
+
+    /// Writes one record followed by a newline.
+    pub fn write<J>(&mut self, record: &J) -> io::Result<()>
+    where
+        J: ResultLike,
+        J::Ok: Serialize,
+        J::Err: Serialize,
+    {
+        match record.as_result() {
+            Ok(v) => serde_json::to_writer(&mut self.writer, v),
+            Err(e) => serde_json::to_writer(&mut self.writer, e),
+        }
+        .map_err(io::Error::from)?;
+        self.writer.write_all(b"\n")
+    }
+
+    /// Writes every record of `records`, stopping at the first error.
+    pub fn write_all<I>(&mut self, records: I) -> io::Result<()>
+    where
+        I: IntoIterator,
+        I::Item: ResultLike,
+        <I::Item as ResultLike>::Ok: Serialize,
+        <I::Item as ResultLike>::Err: Serialize,
+    {
+        for record in records {
+            self.write(&record)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufRead, BufReader, Read};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{JsonResultStream, JsonResultWriter};
+    use crate::error::DecodeError;
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Entry {
+        level: String,
+        msg: String,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    struct Failure {
+        code: u16,
+        reason: String,
+    }
+
+    type Stream<R> = JsonResultStream<R, Entry, Failure>;
+
+    fn entry(msg: &str) -> Entry {
+        Entry {
+            level: "info".to_string(),
+            msg: msg.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_reads_both_branches() {
+        let input = concat!(
+            r#"{"level":"info","msg":"started"}"#,
+            "\n",
+            r#"{"code":500,"reason":"disk full"}"#,
+            "\r\n",
+            r#"{"level":"info","msg":"stopped"}"#,
+        );
+
+        let records: Vec<_> = Stream::new(input.as_bytes())
+            .map(|r| r.unwrap().0)
+            .collect();
+
+        assert_eq!(
+            records,
+            vec![
+                Ok(entry("started")),
+                Err(Failure {
+                    code: 500,
+                    reason: "disk full".to_string()
+                }),
+                Ok(entry("stopped")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bad_lines_report_line_number_and_continue() {
+        let input = concat!(
+            r#"{"level":"info","msg":"a"}"#,
+            "\n",
+            "\n",
+            r#"{"level":"info","mesage":"b"}"#,
+            "\n",
+            "{ not json\n",
+            r#"{"level":"info","msg":"c"}"#,
+            "\n",
+        );
+
+        let mut stream = Stream::new(input.as_bytes());
+
+        assert!(stream.next().unwrap().is_ok());
+
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.line(), 3);
+        assert!(matches!(err.error(), DecodeError::NoMatch(_)));
+        assert!(err.to_string().starts_with("line 3: Failed to parse as"));
+
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.line(), 4);
+        assert!(matches!(err.error(), DecodeError::Json(_)));
+
+        assert_eq!(stream.next().unwrap().unwrap().0, Ok(entry("c")));
+        assert!(stream.next().is_none());
+        assert_eq!(stream.line(), 5);
+    }
+
+    #[test]
+    fn test_io_error_ends_stream() {
+        struct Broken;
+
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("unplugged"))
+            }
+        }
+
+        let reader = io::Cursor::new(b"{\"level\":\"info\",\"msg\":\"a\"}\n".to_vec())
+            .chain(BufReader::new(Broken));
+        let mut stream = Stream::new(reader);
+
+        assert!(stream.next().unwrap().is_ok());
+
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.line(), 2);
+        let DecodeError::Json(e) = err.error() else {
+            panic!("expected an I/O error");
+        };
+        assert!(e.is_io());
+
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_oversized_line_is_skipped() {
+        let input = concat!(
+            r#"{"level":"info","msg":"a"}"#,
+            "\n",
+            r#"{"level":"info","msg":"this line is far too long"}"#,
+            "\n",
+            r#"{"level":"info","msg":"b"}"#,
+        );
+
+        let mut stream =
+            Stream::new(BufReader::with_capacity(4, input.as_bytes())).with_max_line_size(32);
+
+        assert_eq!(stream.next().unwrap().unwrap().0, Ok(entry("a")));
+
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.line(), 2);
+        assert!(matches!(err.error(), DecodeError::Json(_)));
+        assert!(err.to_string().contains("limit of 32 bytes"));
+        assert!(stream.framer.buf().len() <= 32);
+
+        assert_eq!(stream.next().unwrap().unwrap().0, Ok(entry("b")));
+        assert!(stream.next().is_none());
+        assert_eq!(stream.line(), 3);
+    }
+
+    #[test]
+    fn test_unterminated_line_is_not_buffered() {
+        let head = io::Cursor::new(b"{\"level\":\"info\",\"msg\":\"".to_vec());
+        let mut stream =
+            Stream::new(BufReader::new(head.chain(io::repeat(b'x')))).with_max_line_size(1024);
+
+        let err = stream.next().unwrap().unwrap_err();
+        assert_eq!(err.line(), 1);
+        assert!(err.to_string().contains("limit of 1024 bytes"));
+        assert!(stream.framer.buf().capacity() <= 2048);
+    }
+
+    #[test]
+    fn test_writer_accepts_every_flavour() {
+        let mut writer = JsonResultWriter::new(Vec::new());
+
+        writer
+            .write(&r#struct::JsonResult::<Entry, Failure>(Ok(entry("a"))))
+            .unwrap();
+        writer
+            .write(&r#enum::JsonResult::<Entry, Failure>::Err(Failure {
+                code: 1,
+                reason: "x".to_string(),
+            }))
+            .unwrap();
+        writer
+            .write_all(vec![Ok::<_, Failure>(entry("b"))])
+            .unwrap();
+
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            concat!(
+                r#"{"level":"info","msg":"a"}"#,
+                "\n",
+                r#"{"code":1,"reason":"x"}"#,
+                "\n",
+                r#"{"level":"info","msg":"b"}"#,
+                "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let records = vec![
+            Ok(entry("one")),
+            Err(Failure {
+                code: 404,
+                reason: "gone".to_string(),
+            }),
+            Ok(entry("two")),
+        ];
+
+        let mut writer = JsonResultWriter::new(Vec::new());
+        writer.write_all(records.clone()).unwrap();
+        let bytes = writer.into_inner();
+
+        let mut lines = 0;
+        for (read, expected) in Stream::new(BufReader::new(&bytes[..])).zip(&records) {
+            assert_eq!(&read.unwrap().0, expected);
+            lines += 1;
+        }
+        assert_eq!(lines, 3);
+        assert_eq!(bytes.lines().count(), 3);
+    }
+}
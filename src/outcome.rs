@@ -0,0 +1,368 @@
+//! Three-state decoding that keeps payloads fitting neither branch.
+//!
+//! Both `JsonResult` flavours fail when a payload is neither a `T` nor an `E`, and the payload
+//! is lost with the error. [`Outcome`] adds a third variant that keeps it instead: the raw JSON
+//! along with why each branch rejected it. Decoding an `Outcome` only fails on input that is
+//! not valid JSON.
+//!
+//! A payload that parses as both branches becomes [`Outcome::Ok`]. To pick another
+//! [`Ambiguity`] rule, decode a `Repr<Outcome<T, E>, Untagged<P>>` instead; under `Strict` an
+//! ambiguous payload is an error rather than a fourth state.
+//!
+//! ```rust
+//! use json_result::outcome::Outcome;
+//! use serde_json::json;
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Quote {
+//!     price: f64,
+//! }
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct VendorError {
+//!     code: u32,
+//! }
+//!
+//! let body = json!({"status": "maintenance"});
+//! let Outcome::Unrecognized(failure) = Outcome::<Quote, VendorError>::from(body) else {
+//!     panic!("expected an unrecognized body");
+//! };
+//!
+//! assert_eq!(failure.payload(), &json!({"status": "maintenance"}));
+//! assert!(failure.error().ok().type_name().ends_with("Quote"));
+//! ```
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::r#enum::JsonResult;
+use crate::error::{DecodeError, DecodeFailure};
+use crate::repr::untagged::{self, Ambiguity};
+use crate::repr::{AmbiguityPolicy, Repr, Untagged};
+use crate::tape::Tape;
+
+/// The result of decoding a payload as `T` first and `E` second, or neither.
+#[derive(Debug)]
+pub enum Outcome<T, E> {
+    /// The payload parsed as `T`.
+    Ok(T),
+    /// The payload parsed as `E`.
+    Err(E),
+    /// The payload parsed as neither; it is kept with both branch errors.
+    Unrecognized(DecodeFailure),
+}
+
+impl<T, E> Outcome<T, E> {
+    /// Whether the payload parsed as `T`.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Outcome::Ok(_))
+    }
+
+    /// Whether the payload parsed as `E`.
+    pub fn is_err(&self) -> bool {
+        matches!(self, Outcome::Err(_))
+    }
+
+    /// Whether the payload parsed as neither branch.
+    pub fn is_unrecognized(&self) -> bool {
+        matches!(self, Outcome::Unrecognized(_))
+    }
+
+    /// Splits off the unrecognized case, leaving a plain `Result` for the other two.
+    pub fn recognized(self) -> Result<Result<T, E>, DecodeFailure> {
+        match self {
+            Outcome::Ok(v) => Ok(Ok(v)),
+            Outcome::Err(e) => Ok(Err(e)),
+            Outcome::Unrecognized(failure) => Err(failure),
+        }
+    }
+
+    /// Sorts the result of an untagged decode, building the kept payload only on a mismatch.
+    ///
+    /// Fails only with errors other than [`DecodeError::NoMatch`], such as an ambiguous payload
+    /// under `Strict`.
+    fn from_decoded(
+        decoded: Result<Result<T, E>, DecodeError>,
+        payload: impl FnOnce() -> Value,
+    ) -> Result<Self, DecodeError> {
+        match decoded {
+            Ok(Ok(v)) => Ok(Outcome::Ok(v)),
+            Ok(Err(e)) => Ok(Outcome::Err(e)),
+            Err(DecodeError::NoMatch(error)) => {
+                Ok(Outcome::Unrecognized(DecodeFailure::new(payload(), error)))
+            }
+            Err(other) => Err(other),
+        }
+    }
+
+    /// Like [`Outcome::from_decoded`], for a `PreferOk` decode.
+    ///
+    /// Such a decode cannot fail with anything but [`DecodeError::NoMatch`]: the input is
+    /// already buffered, so there is no parse error left to report, and only `Reject` and
+    /// `BestFit` turn a payload that fits both branches into [`DecodeError::Ambiguous`], where
+    /// `PreferOk` returns the first branch that fits. The remaining variants are produced by
+    /// callers of the decode, never by it.
+    fn from_preferred(
+        decoded: Result<Result<T, E>, DecodeError>,
+        payload: impl FnOnce() -> Value,
+    ) -> Self {
+        Outcome::from_decoded(decoded, payload).unwrap_or_else(|other| {
+            unreachable!("a `PreferOk` decode only fails with `NoMatch`: {other}")
+        })
+    }
+}
+
+impl<T, E> From<Value> for Outcome<T, E>
+where
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+{
+    /// Decodes `value` as `T`, then `E`. A payload that fits neither is moved into
+    /// [`Outcome::Unrecognized`] without being copied.
+    fn from(value: Value) -> Self {
+        let decoded = untagged::decode(&value, Ambiguity::PreferOk);
+        Outcome::from_preferred(decoded, || value)
+    }
+}
+
+impl<'a, T, E> From<&'a Value> for Outcome<T, E>
+where
+    T: Deserialize<'a>,
+    E: Deserialize<'a>,
+{
+    /// Like the conversion from an owned `Value`, but `T` and `E` may borrow from `value`.
+    fn from(value: &'a Value) -> Self {
+        Outcome::from_preferred(untagged::decode(value, Ambiguity::PreferOk), || {
+            value.clone()
+        })
+    }
+}
+
+impl<'de, T, E> Deserialize<'de> for Outcome<T, E>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let tape = Tape::deserialize(deserializer)?;
+        let decoded = untagged::decode(&tape, Ambiguity::PreferOk);
+        Ok(Outcome::from_preferred(decoded, || tape.to_value()))
+    }
+}
+
+impl<'de, T, E, P> Deserialize<'de> for Repr<Outcome<T, E>, Untagged<P>>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+    P: AmbiguityPolicy,
+{
+    /// Like decoding a plain [`Outcome`], but a payload that parses as both branches is settled
+    /// by `P`.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let tape = Tape::deserialize(deserializer)?;
+        let decoded = untagged::decode(&tape, P::ON_AMBIGUITY);
+        Outcome::from_decoded(decoded, || tape.to_value())
+            .map(Repr::new)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl<T, E, P> TryFrom<Value> for Repr<Outcome<T, E>, Untagged<P>>
+where
+    T: DeserializeOwned,
+    E: DeserializeOwned,
+    P: AmbiguityPolicy,
+{
+    type Error = DecodeError;
+
+    /// Decodes `value` under `P`. Fails only where `P` rejects an ambiguous payload.
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let decoded = untagged::decode(&value, P::ON_AMBIGUITY);
+        Outcome::from_decoded(decoded, || value).map(Repr::new)
+    }
+}
+
+impl<T, E, P> Serialize for Repr<Outcome<T, E>, Untagged<P>>
+where
+    T: Serialize,
+    E: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<T, E> Serialize for Outcome<T, E>
+where
+    T: Serialize,
+    E: Serialize,
+{
+    /// Writes whichever branch is present, or the kept payload as it was received.
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Outcome::Ok(v) => v.serialize(serializer),
+            Outcome::Err(e) => e.serialize(serializer),
+            Outcome::Unrecognized(failure) => failure.payload().serialize(serializer),
+        }
+    }
+}
+
+impl<T, E> From<Outcome<T, E>> for Value
+where
+    T: Serialize,
+    E: Serialize,
+{
+    fn from(outcome: Outcome<T, E>) -> Self {
+        match outcome {
+            Outcome::Ok(v) => serde_json::json!(v),
+            Outcome::Err(e) => serde_json::json!(e),
+            Outcome::Unrecognized(failure) => failure.into_payload(),
+        }
+    }
+}
+
+impl<T, E> From<Result<T, E>> for Outcome<T, E> {
+    fn from(r: Result<T, E>) -> Self {
+        match r {
+            Ok(v) => Outcome::Ok(v),
+            Err(e) => Outcome::Err(e),
+        }
+    }
+}
+
+impl<T, E> From<JsonResult<T, E>> for Outcome<T, E> {
+    fn from(jr: JsonResult<T, E>) -> Self {
+        match jr {
+            JsonResult::Ok(v) => Outcome::Ok(v),
+            JsonResult::Err(e) => Outcome::Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::Outcome;
+    use crate::error::DecodeError;
+    use crate::repr::test_support::{Amb, BadE, GoodT};
+    use crate::repr::{PreferErr, Repr, Strict, Untagged};
+
+    type Decoded = Outcome<GoodT, BadE>;
+
+    #[test]
+    fn test_branches_decode_as_before() {
+        assert!(matches!(
+            Decoded::from(json!({ "x": 1 })),
+            Outcome::Ok(GoodT { x: 1 })
+        ));
+        assert!(matches!(
+            Decoded::from(json!({ "msg": "no" })),
+            Outcome::Err(BadE { .. })
+        ));
+    }
+
+    #[test]
+    fn test_null_value_is_kept() {
+        let outcome = Decoded::from(json!(null));
+
+        assert!(outcome.is_unrecognized());
+        let failure = outcome.recognized().unwrap_err();
+        assert_eq!(failure.payload(), &json!(null));
+        assert!(failure.error().ok().type_name().ends_with("GoodT"));
+        assert!(failure.error().err().type_name().ends_with("BadE"));
+    }
+
+    #[test]
+    fn test_invalid_json_structure_is_kept() {
+        let outcome: Decoded = json!([1, 2, 3]).try_into().unwrap();
+
+        let Outcome::Unrecognized(failure) = outcome else {
+            panic!("expected Unrecognized");
+        };
+        assert!(failure.to_string().contains("Failed to parse as"));
+        assert_eq!(failure.into_payload(), json!([1, 2, 3]));
+    }
+
+    #[test]
+    fn test_deserialize_keeps_nested_payload() {
+        #[derive(Debug, Deserialize)]
+        struct Envelope {
+            id: u32,
+            body: Decoded,
+        }
+
+        let input = r#"{"id": 9, "body": {"x": "seven", "trace": [1, {"a": null}]}}"#;
+        let envelope: Envelope = serde_json::from_str(input).unwrap();
+
+        assert_eq!(envelope.id, 9);
+        let failure = envelope.body.recognized().unwrap_err();
+        assert_eq!(
+            failure.payload(),
+            &json!({ "x": "seven", "trace": [1, { "a": null }] })
+        );
+        assert_eq!(failure.error().ok().path(), ".x");
+    }
+
+    #[test]
+    fn test_invalid_json_still_fails() {
+        assert!(serde_json::from_str::<Decoded>("{ nope").is_err());
+    }
+
+    #[test]
+    fn test_serializes_payload_back() {
+        let raw = json!({ "unexpected": ["shape"] });
+
+        let outcome = Decoded::from(raw.clone());
+        assert_eq!(serde_json::to_value(&outcome).unwrap(), raw);
+
+        let value: serde_json::Value = outcome.into();
+        assert_eq!(value, raw);
+
+        let ok: serde_json::Value = Decoded::from(Ok(GoodT { x: 2 })).into();
+        assert_eq!(ok, json!({ "x": 2 }));
+    }
+
+    #[test]
+    fn test_borrows_from_value() {
+        let value = json!("borrowed");
+
+        let outcome = Outcome::<&str, u32>::from(&value);
+        assert!(matches!(outcome, Outcome::Ok("borrowed")));
+
+        let outcome = Outcome::<u32, bool>::from(&value);
+        assert_eq!(outcome.recognized().unwrap_err().payload(), &value);
+    }
+
+    #[test]
+    fn test_ambiguity_policy() {
+        type Either<P> = Repr<Outcome<Amb, Amb>, Untagged<P>>;
+
+        let outcome: Either<PreferErr> = serde_json::from_str(r#"{"x": 1}"#).unwrap();
+        assert!(matches!(*outcome, Outcome::Err(Amb { x: 1 })));
+
+        let err = serde_json::from_str::<Either<Strict>>(r#"{"x": 1}"#).unwrap_err();
+        assert!(err.to_string().contains("Ambiguous payload"));
+
+        let err = Either::<Strict>::try_from(json!({ "x": 1 })).unwrap_err();
+        assert!(matches!(err, DecodeError::Ambiguous(_)));
+
+        let outcome = Either::<Strict>::try_from(json!({ "y": 1 })).unwrap();
+        assert!(outcome.is_unrecognized());
+        assert_eq!(serde_json::to_value(&outcome).unwrap(), json!({ "y": 1 }));
+    }
+}
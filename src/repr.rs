@@ -0,0 +1,221 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub mod adjacent;
+pub mod compact;
+pub mod external;
+pub mod internal;
+pub mod presence;
+pub mod untagged;
+
+pub use adjacent::{Adjacent, EnvelopeKeys, StatusData};
+pub use compact::Compact;
+pub use external::{External, OkErr, OkError, TagKeys};
+pub use internal::{Discriminator, Internal, SuccessFlag, TagValue, TypeField};
+pub use presence::{ErrorField, Presence, PresenceKeys};
+pub use untagged::{
+    Ambiguity, AmbiguityPolicy, BestFit, PreferErr, PreferOk, Strict, Tie, Untagged,
+};
+
+/// Common view over `r#enum::JsonResult`, `r#struct::JsonResult` and a plain `Result`.
+///
+/// Wire representations only need to move a `Result<T, E>` in and out of the wrapped type,
+/// so implementing this trait is all it takes for a type to be used with [`Repr`].
+pub trait ResultLike {
+    /// The success type.
+    type Ok;
+    /// The error type.
+    type Err;
+
+    /// Builds the value from a plain `Result`.
+    fn from_result(result: Result<Self::Ok, Self::Err>) -> Self;
+
+    /// Borrows the contained value as a plain `Result`.
+    fn as_result(&self) -> Result<&Self::Ok, &Self::Err>;
+
+    /// Consumes the value and returns the contained plain `Result`.
+    fn into_result(self) -> Result<Self::Ok, Self::Err>;
+}
+
+impl<T, E> ResultLike for Result<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn from_result(result: Result<T, E>) -> Self {
+        result
+    }
+
+    fn as_result(&self) -> Result<&T, &E> {
+        self.as_ref()
+    }
+
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+}
+
+/// A wire representation for a `JsonResult`.
+///
+/// Implementors decide how the `Ok` and `Err` branches are laid out on the wire and
+/// how the branch is picked back up when deserializing.
+pub trait Representation {
+    /// Serializes whichever branch is present.
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer;
+
+    /// Deserializes either branch.
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>;
+}
+
+/// Repr<J, R>
+///
+/// Wraps a `JsonResult` (either flavour) and serializes it with the wire representation `R`
+/// instead of the default untagged shape.
+///
+/// # Examples
+///
+/// ```rust
+/// use json_result::r#struct::JsonResult;
+/// use json_result::repr::{External, OkError, Repr};
+///
+/// let jr = Repr::<_, External<OkError>>::new(JsonResult::<i32, String>(Ok(1)));
+/// assert_eq!(serde_json::to_string(&jr).unwrap(), r#"{"ok":1}"#);
+///
+/// let parsed: Repr<JsonResult<i32, String>, External<OkError>> =
+///     serde_json::from_str(r#"{"error":"boom"}"#).unwrap();
+/// assert_eq!(parsed.0.0, Err("boom".to_string()));
+/// ```
+pub struct Repr<J, R>(pub J, PhantomData<fn() -> R>);
+
+impl<J, R> Repr<J, R> {
+    /// Wraps `inner` so that it is (de)serialized with the representation `R`.
+    pub fn new(inner: J) -> Self {
+        Repr(inner, PhantomData)
+    }
+
+    /// Returns the wrapped value.
+    pub fn into_inner(self) -> J {
+        self.0
+    }
+}
+
+impl<J, R> Serialize for Repr<J, R>
+where
+    J: ResultLike,
+    J::Ok: Serialize,
+    J::Err: Serialize,
+    R: Representation,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        R::serialize(self.0.as_result(), serializer)
+    }
+}
+
+impl<'de, J, R> Deserialize<'de> for Repr<J, R>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+    R: Representation,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        R::deserialize(deserializer).map(|r| Repr::new(J::from_result(r)))
+    }
+}
+
+impl<J, R> TryFrom<serde_json::Value> for Repr<J, R>
+where
+    J: ResultLike,
+    J::Ok: DeserializeOwned,
+    J::Err: DeserializeOwned,
+    R: Representation,
+{
+    type Error = serde_json::Error;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value)
+    }
+}
+
+impl<J, R> From<Repr<J, R>> for serde_json::Value
+where
+    J: ResultLike,
+    J::Ok: Serialize,
+    J::Err: Serialize,
+    R: Representation,
+{
+    fn from(value: Repr<J, R>) -> Self {
+        serde_json::json!(value)
+    }
+}
+
+impl<J: fmt::Debug, R> fmt::Debug for Repr<J, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Repr").field(&self.0).finish()
+    }
+}
+
+impl<J, R> Deref for Repr<J, R> {
+    type Target = J;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<J, R> DerefMut for Repr<J, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<J, R> From<J> for Repr<J, R> {
+    fn from(inner: J) -> Self {
+        Repr::new(inner)
+    }
+}
+
+/// Fixtures shared by the representation tests.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use serde::{Deserialize, Serialize};
+
+    use super::Repr;
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub(crate) struct GoodT {
+        pub(crate) x: u32,
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub(crate) struct BadE {
+        pub(crate) msg: String,
+    }
+
+    /// Same shape as [`GoodT`], so a payload always fits both.
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+    pub(crate) struct Amb {
+        pub(crate) x: u32,
+    }
+
+    pub(crate) type StructRepr<T, E, R> = Repr<r#struct::JsonResult<T, E>, R>;
+    pub(crate) type EnumRepr<T, E, R> = Repr<r#enum::JsonResult<T, E>, R>;
+}
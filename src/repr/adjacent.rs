@@ -0,0 +1,562 @@
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, IgnoredAny, MapAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, forward_to_deserialize_any};
+
+use super::Representation;
+use crate::tape::Tape;
+
+/// Field names and tag values used by the [`Adjacent`] representation.
+///
+/// ```rust
+/// use json_result::repr::EnvelopeKeys;
+///
+/// /// `{"result": "success", "payload": ...}` / `{"result": "failure", "reason": ...}`
+/// struct ResultPayload;
+///
+/// impl EnvelopeKeys for ResultPayload {
+///     const TAG: &'static str = "result";
+///     const OK_TAG: &'static str = "success";
+///     const ERR_TAG: &'static str = "failure";
+///     const OK_CONTENT: &'static str = "payload";
+///     const ERR_CONTENT: &'static str = "reason";
+/// }
+/// ```
+pub trait EnvelopeKeys {
+    /// Name of the field holding the tag.
+    const TAG: &'static str;
+    /// Tag value marking the `Ok` branch.
+    const OK_TAG: &'static str;
+    /// Tag value marking the `Err` branch.
+    const ERR_TAG: &'static str;
+    /// Name of the field holding the `Ok` value.
+    const OK_CONTENT: &'static str;
+    /// Name of the field holding the `Err` value. May be the same as `OK_CONTENT`.
+    const ERR_CONTENT: &'static str;
+}
+
+/// `{"status": "ok", "data": ...}` / `{"status": "error", "error": ...}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StatusData;
+
+impl EnvelopeKeys for StatusData {
+    const TAG: &'static str = "status";
+    const OK_TAG: &'static str = "ok";
+    const ERR_TAG: &'static str = "error";
+    const OK_CONTENT: &'static str = "data";
+    const ERR_CONTENT: &'static str = "error";
+}
+
+/// Adjacent<K>
+///
+/// Adjacently tagged representation: the branch is named by the `K::TAG` field and the value
+/// lives in a separate payload field, e.g. `{"status": "ok", "data": 1}`.
+///
+/// The branch is chosen from the tag alone; `T` and `E` are never tried in turn. Fields other
+/// than the tag and the payload field are ignored. A missing payload field is treated as
+/// `null`, so `()` and `Option` payloads may omit it.
+///
+/// A payload that comes before the tag is recorded as it was read and decoded once the tag is
+/// known, so field order never changes what a payload decodes to, in any format.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Adjacent<K = StatusData>(PhantomData<K>);
+
+const NAME: &str = "JsonResult";
+
+impl<K: EnvelopeKeys> Representation for Adjacent<K> {
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct(NAME, 2)?;
+        match result {
+            Ok(v) => {
+                state.serialize_field(K::TAG, K::OK_TAG)?;
+                state.serialize_field(K::OK_CONTENT, v)?;
+            }
+            Err(e) => {
+                state.serialize_field(K::TAG, K::ERR_TAG)?;
+                state.serialize_field(K::ERR_CONTENT, e)?;
+            }
+        }
+        state.end()
+    }
+
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(AdjacentVisitor::<T, E, K>(PhantomData))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Branch {
+    Ok,
+    Err,
+}
+
+impl Branch {
+    fn from_tag<K: EnvelopeKeys, Er: de::Error>(tag: &str) -> Result<Self, Er> {
+        if tag == K::OK_TAG {
+            Ok(Branch::Ok)
+        } else if tag == K::ERR_TAG {
+            Ok(Branch::Err)
+        } else {
+            Err(Er::custom(format!(
+                "unknown `{}` value `{}`, expected `{}` or `{}`",
+                K::TAG,
+                tag,
+                K::OK_TAG,
+                K::ERR_TAG
+            )))
+        }
+    }
+}
+
+struct AdjacentVisitor<T, E, K>(PhantomData<(T, E, K)>);
+
+impl<'de, T, E, K> Visitor<'de> for AdjacentVisitor<T, E, K>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+    K: EnvelopeKeys,
+{
+    type Value = Result<T, E>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an object with a `{}` field", K::TAG)
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut branch: Option<Branch> = None;
+        let mut decoded: Option<Result<T, E>> = None;
+        // Payloads seen before the tag, keyed by the field they came from. When both branches
+        // share a content field, only `ok_buf` is used.
+        let mut ok_buf: Option<Tape<'de>> = None;
+        let mut err_buf: Option<Tape<'de>> = None;
+        let mut seen_ok = false;
+        let mut seen_err = false;
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == K::TAG {
+                if branch.is_some() {
+                    return Err(de::Error::duplicate_field(K::TAG));
+                }
+                let tag = map.next_value::<String>()?;
+                branch = Some(Branch::from_tag::<K, A::Error>(&tag)?);
+            } else if key == K::OK_CONTENT || key == K::ERR_CONTENT {
+                let (seen, field) = if key == K::OK_CONTENT {
+                    (&mut seen_ok, K::OK_CONTENT)
+                } else {
+                    (&mut seen_err, K::ERR_CONTENT)
+                };
+                if core::mem::replace(seen, true) {
+                    return Err(de::Error::duplicate_field(field));
+                }
+                match branch {
+                    Some(Branch::Ok) if key == K::OK_CONTENT => {
+                        decoded = Some(Ok(map.next_value()?));
+                    }
+                    Some(Branch::Err) if key == K::ERR_CONTENT => {
+                        decoded = Some(Err(map.next_value()?));
+                    }
+                    Some(_) => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                    None => {
+                        let value = map.next_value::<Tape<'de>>()?;
+                        if key == K::OK_CONTENT {
+                            ok_buf = Some(value);
+                        } else {
+                            err_buf = Some(value);
+                        }
+                    }
+                }
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        if let Some(result) = decoded {
+            return Ok(result);
+        }
+
+        let branch = branch.ok_or_else(|| de::Error::missing_field(K::TAG))?;
+        match branch {
+            Branch::Ok => from_payload::<T, A::Error>(ok_buf, K::OK_CONTENT).map(Ok),
+            Branch::Err => {
+                let buffered = if K::OK_CONTENT == K::ERR_CONTENT {
+                    ok_buf
+                } else {
+                    err_buf
+                };
+                from_payload::<E, A::Error>(buffered, K::ERR_CONTENT).map(Err)
+            }
+        }
+    }
+}
+
+fn from_payload<'de, V, Er>(buffered: Option<Tape<'de>>, field: &'static str) -> Result<V, Er>
+where
+    V: Deserialize<'de>,
+    Er: de::Error,
+{
+    match buffered {
+        Some(tape) => V::deserialize(tape.replay_as::<Er>()),
+        None => V::deserialize(MissingPayload {
+            field,
+            error: PhantomData,
+        }),
+    }
+}
+
+/// Fails every listed `deserialize_*` method with `missing_field`.
+macro_rules! missing {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Er> {
+                Err(Er::missing_field(self.field))
+            }
+        )*
+    };
+}
+
+/// Stands in for an absent payload field. It reads as `null` to types that accept anything,
+/// `()` and `Option`, and fails with `missing_field` for types that need a value of some
+/// shape. Any other failure is the payload type's own.
+struct MissingPayload<Er> {
+    field: &'static str,
+    error: PhantomData<Er>,
+}
+
+impl<'de, Er: de::Error> Deserializer<'de> for MissingPayload<Er> {
+    type Error = Er;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Er> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Er> {
+        visitor.visit_none()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Er> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Er> {
+        Err(Er::missing_field(self.field))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Er> {
+        Err(Er::missing_field(self.field))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, Er> {
+        Err(Er::missing_field(self.field))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, Er> {
+        Err(Er::missing_field(self.field))
+    }
+
+    missing! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_seq
+        deserialize_map deserialize_identifier
+    }
+
+    forward_to_deserialize_any! {
+        unit unit_struct ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{Adjacent, EnvelopeKeys};
+    use crate::repr::test_support::{Amb, BadE, EnumRepr, GoodT, StructRepr};
+    use crate::{r#enum, r#struct};
+
+    struct Shared;
+
+    impl EnvelopeKeys for Shared {
+        const TAG: &'static str = "kind";
+        const OK_TAG: &'static str = "success";
+        const ERR_TAG: &'static str = "failure";
+        const OK_CONTENT: &'static str = "body";
+        const ERR_CONTENT: &'static str = "body";
+    }
+
+    #[test]
+    fn test_ok_serialization() {
+        let jr = StructRepr::<i32, String, Adjacent>::new(r#struct::JsonResult(Ok(100)));
+        let s = serde_json::to_string(&jr).unwrap();
+        assert_eq!(s, r#"{"status":"ok","data":100}"#);
+    }
+
+    #[test]
+    fn test_err_serialization() {
+        let jr = StructRepr::<i32, &str, Adjacent>::new(r#struct::JsonResult(Err("boom")));
+        let s = serde_json::to_string(&jr).unwrap();
+        assert_eq!(s, r#"{"status":"error","error":"boom"}"#);
+    }
+
+    #[test]
+    fn test_struct_round_trip_ok() {
+        let original =
+            StructRepr::<GoodT, BadE, Adjacent>::new(r#struct::JsonResult(Ok(GoodT { x: 55 })));
+        let json = serde_json::to_value(&original).unwrap();
+        let parsed: StructRepr<GoodT, BadE, Adjacent> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(parsed.0.0, Ok(GoodT { x: 55 }));
+    }
+
+    #[test]
+    fn test_struct_round_trip_err() {
+        let original = StructRepr::<GoodT, BadE, Adjacent>::new(r#struct::JsonResult(Err(BadE {
+            msg: "x".into(),
+        })));
+        let json = serde_json::to_value(&original).unwrap();
+        let parsed: StructRepr<GoodT, BadE, Adjacent> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(parsed.0.0, Err(BadE { msg: "x".into() }));
+    }
+
+    #[test]
+    fn test_enum_round_trip_with_shared_payload_field() {
+        let original: EnumRepr<GoodT, BadE, Adjacent<Shared>> =
+            r#enum::JsonResult::Err(BadE { msg: "boom".into() }).into();
+
+        let json: serde_json::Value = original.into();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "failure", "body": { "msg": "boom" } })
+        );
+
+        let parsed = EnumRepr::<GoodT, BadE, Adjacent<Shared>>::try_from(json).unwrap();
+        match parsed.into_inner() {
+            r#enum::JsonResult::Err(e) => assert_eq!(e.msg, "boom"),
+            _ => panic!("Round trip for E failed"),
+        }
+    }
+
+    #[test]
+    fn test_tag_after_payload() {
+        let json = r#"{"data":{"x":7},"error":{"msg":"ignored"},"status":"ok"}"#;
+        let jr: StructRepr<GoodT, BadE, Adjacent> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(jr.0.0, Ok(GoodT { x: 7 }));
+    }
+
+    #[test]
+    fn test_shared_payload_field_before_tag() {
+        let json = r#"{"body":{"msg":"late"},"kind":"failure"}"#;
+        let jr: StructRepr<GoodT, BadE, Adjacent<Shared>> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            jr.0.0,
+            Err(BadE {
+                msg: "late".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_borrows_from_input() {
+        let input = r#"{"error": "before the tag", "status": "error"}"#;
+        let jr: StructRepr<u32, &str, Adjacent> = serde_json::from_str(input).unwrap();
+        assert_eq!(jr.0.0, Err("before the tag"));
+
+        let input = r#"{"status": "ok", "data": "after the tag"}"#;
+        let jr: StructRepr<&str, u32, Adjacent> = serde_json::from_str(input).unwrap();
+        assert_eq!(jr.0.0, Ok("after the tag"));
+    }
+
+    #[test]
+    fn test_duplicate_payload_field_fails() {
+        for json in [
+            r#"{"data":{"x":1},"data":{"x":2},"status":"ok"}"#,
+            r#"{"status":"ok","data":{"x":1},"data":{"x":2}}"#,
+            r#"{"data":{"x":1},"status":"ok","data":{"x":2}}"#,
+        ] {
+            let msg = serde_json::from_str::<StructRepr<GoodT, BadE, Adjacent>>(json)
+                .unwrap_err()
+                .to_string();
+            assert!(msg.contains("duplicate field `data`"), "{msg}");
+        }
+
+        let json = r#"{"body":{"x":1},"kind":"success","body":{"x":2}}"#;
+        let msg = serde_json::from_str::<StructRepr<GoodT, BadE, Adjacent<Shared>>>(json)
+            .unwrap_err()
+            .to_string();
+        assert!(msg.contains("duplicate field `body`"));
+    }
+
+    #[test]
+    fn test_ambiguous_payload_follows_tag() {
+        let json = serde_json::json!({ "status": "error", "error": { "x": 10 } });
+        let jr: StructRepr<Amb, Amb, Adjacent> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(jr.0.0, Err(Amb { x: 10 }));
+    }
+
+    #[test]
+    fn test_extra_fields_are_ignored() {
+        let json = serde_json::json!({ "status": "ok", "data": { "x": 1 }, "request_id": "abc" });
+        let jr = EnumRepr::<GoodT, BadE, Adjacent>::try_from(json).unwrap();
+
+        assert!(matches!(jr.0, r#enum::JsonResult::Ok(GoodT { x: 1 })));
+    }
+
+    #[test]
+    fn test_missing_payload_for_unit() {
+        let json = serde_json::json!({ "status": "ok" });
+        let jr = StructRepr::<(), BadE, Adjacent>::try_from(json).unwrap();
+
+        assert_eq!(jr.0.0, Ok(()));
+    }
+
+    #[test]
+    fn test_missing_payload_fails() {
+        let json = serde_json::json!({ "status": "ok" });
+        let msg = StructRepr::<GoodT, BadE, Adjacent>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("missing field `data`"));
+    }
+
+    #[test]
+    fn test_missing_payload_keeps_payload_error() {
+        /// Accepts an absent value only to reject it with its own message.
+        #[derive(Debug, PartialEq)]
+        struct Required(u32);
+
+        impl<'de> Deserialize<'de> for Required {
+            fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                Option::<u32>::deserialize(d)?
+                    .map(Required)
+                    .ok_or_else(|| serde::de::Error::custom("a code is required"))
+            }
+        }
+
+        let json = serde_json::json!({ "status": "error" });
+        let msg = serde_json::from_value::<StructRepr<GoodT, Required, Adjacent>>(json)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(msg, "a code is required");
+
+        let json = serde_json::json!({ "status": "ok" });
+        let jr: StructRepr<Option<GoodT>, BadE, Adjacent> = serde_json::from_value(json).unwrap();
+        assert_eq!(jr.0.0, Ok(None));
+    }
+
+    #[test]
+    fn test_payload_before_tag_keeps_payload_error() {
+        let before = r#"{"data":{"x":"seven"},"status":"ok"}"#;
+        let after = r#"{"status":"ok","data":{"x":"seven"}}"#;
+
+        let before = serde_json::from_str::<StructRepr<GoodT, BadE, Adjacent>>(before).unwrap_err();
+        let after = serde_json::from_str::<StructRepr<GoodT, BadE, Adjacent>>(after).unwrap_err();
+        assert!(
+            before
+                .to_string()
+                .starts_with(r#"invalid type: string "seven", expected u32"#)
+        );
+        assert!(
+            after
+                .to_string()
+                .starts_with(r#"invalid type: string "seven", expected u32"#)
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_payload_before_tag_keeps_cbor_values() {
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, serde::Serialize, Deserialize, PartialEq)]
+        struct Totals {
+            by_id: BTreeMap<u32, String>,
+            sum: u128,
+        }
+
+        let totals = Totals {
+            by_id: BTreeMap::from([(7, "seven".to_string())]),
+            sum: u128::MAX,
+        };
+
+        // `data` is written before `status`, which a derived struct cannot express.
+        let mut envelope = BTreeMap::new();
+        envelope.insert("data", ciborium::Value::serialized(&totals).unwrap());
+        envelope.insert("status", ciborium::Value::Text("ok".to_string()));
+        let mut buf = Vec::new();
+        ciborium::into_writer(&envelope, &mut buf).unwrap();
+
+        let jr: StructRepr<Totals, BadE, Adjacent> = ciborium::from_reader(buf.as_slice()).unwrap();
+        assert_eq!(jr.0.0, Ok(totals));
+    }
+
+    #[test]
+    fn test_unknown_tag_fails() {
+        let json = serde_json::json!({ "status": "pending", "data": { "x": 1 } });
+        let msg = StructRepr::<GoodT, BadE, Adjacent>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("unknown `status` value `pending`"));
+        assert!(msg.contains("`ok` or `error`"));
+    }
+
+    #[test]
+    fn test_missing_tag_fails() {
+        let json = serde_json::json!({ "data": { "x": 1 } });
+        let msg = StructRepr::<GoodT, BadE, Adjacent>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("missing field `status`"));
+    }
+
+    #[test]
+    fn test_wrong_payload_shape_fails() {
+        let json = serde_json::json!({ "status": "error", "error": { "x": 1 } });
+        let result = EnumRepr::<GoodT, BadE, Adjacent>::try_from(json);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{External, OkErr, Representation, Untagged};
+
+/// Compact<H>
+///
+/// Uses `H` (untagged by default) in human-readable formats such as JSON, and an explicit
+/// variant index (`0` for `Ok`, `1` for `Err`) in binary formats such as bincode and postcard,
+/// which cannot try `T` and then `E`.
+///
+/// The choice follows `is_human_readable()` on the serializer and deserializer, so the same
+/// type can be cached in bincode and served as JSON.
+///
+/// # Examples
+///
+/// ```rust
+/// use json_result::r#struct::JsonResult;
+/// use json_result::repr::{Compact, Repr};
+///
+/// type Cached = Repr<JsonResult<u32, String>, Compact>;
+///
+/// let jr = Cached::new(JsonResult(Ok(7)));
+/// assert_eq!(serde_json::to_string(&jr).unwrap(), "7");
+///
+/// let back: Cached = serde_json::from_str(r#""boom""#).unwrap();
+/// assert_eq!(back.0.0, Err("boom".to_string()));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Compact<H = Untagged>(PhantomData<H>);
+
+impl<H: Representation> Representation for Compact<H> {
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer,
+    {
+        if serializer.is_human_readable() {
+            H::serialize(result, serializer)
+        } else {
+            External::<OkErr>::serialize(result, serializer)
+        }
+    }
+
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            H::deserialize(deserializer)
+        } else {
+            External::<OkErr>::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::Compact;
+    use crate::repr::Adjacent;
+    use crate::repr::test_support::{Amb, EnumRepr, StructRepr};
+    use crate::{r#enum, r#struct};
+
+    fn bincode_round_trip<T, E>(jr: &StructRepr<T, E, Compact>) -> StructRepr<T, E, Compact>
+    where
+        T: Serialize + serde::de::DeserializeOwned,
+        E: Serialize + serde::de::DeserializeOwned,
+    {
+        let config = bincode::config::standard();
+        let bytes = bincode::serde::encode_to_vec(jr, config).unwrap();
+        bincode::serde::decode_from_slice(&bytes, config).unwrap().0
+    }
+
+    #[test]
+    fn test_bincode_keeps_branch_of_ambiguous_payload() {
+        let ok = StructRepr::<Amb, Amb, Compact>::new(r#struct::JsonResult(Ok(Amb { x: 1 })));
+        let err = StructRepr::<Amb, Amb, Compact>::new(r#struct::JsonResult(Err(Amb { x: 2 })));
+
+        assert_eq!(bincode_round_trip(&ok).0.0, Ok(Amb { x: 1 }));
+        assert_eq!(bincode_round_trip(&err).0.0, Err(Amb { x: 2 }));
+    }
+
+    #[test]
+    fn test_bincode_writes_variant_index() {
+        let jr = StructRepr::<u8, u8, Compact>::new(r#struct::JsonResult(Err(5)));
+        let bytes = bincode::serde::encode_to_vec(&jr, bincode::config::standard()).unwrap();
+
+        assert_eq!(bytes, vec![1, 5]);
+    }
+
+    #[test]
+    fn test_postcard_round_trip() {
+        let ok = EnumRepr::<String, u32, Compact>::new(r#enum::JsonResult::Ok("fine".to_string()));
+        let bytes = postcard::to_allocvec(&ok).unwrap();
+        let back: EnumRepr<String, u32, Compact> = postcard::from_bytes(&bytes).unwrap();
+        assert!(matches!(back.0, r#enum::JsonResult::Ok(ref s) if s == "fine"));
+
+        let err = EnumRepr::<String, u32, Compact>::new(r#enum::JsonResult::Err(404));
+        let bytes = postcard::to_allocvec(&err).unwrap();
+        let back: EnumRepr<String, u32, Compact> = postcard::from_bytes(&bytes).unwrap();
+        assert!(matches!(back.0, r#enum::JsonResult::Err(404)));
+    }
+
+    #[test]
+    fn test_postcard_rejects_unknown_index() {
+        let bytes = [2, 0];
+        let result = postcard::from_bytes::<EnumRepr<u32, u32, Compact>>(&bytes);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_json_stays_untagged() {
+        let jr = StructRepr::<Amb, String, Compact>::new(r#struct::JsonResult(Ok(Amb { x: 3 })));
+        assert_eq!(serde_json::to_string(&jr).unwrap(), r#"{"x":3}"#);
+
+        let back: StructRepr<Amb, String, Compact> = serde_json::from_str(r#""boom""#).unwrap();
+        assert_eq!(back.0.0, Err("boom".to_string()));
+    }
+
+    #[test]
+    fn test_human_readable_representation_is_configurable() {
+        type Envelope = StructRepr<u32, String, Compact<Adjacent>>;
+
+        let jr = Envelope::new(r#struct::JsonResult(Ok(1)));
+        assert_eq!(
+            serde_json::to_string(&jr).unwrap(),
+            r#"{"status":"ok","data":1}"#
+        );
+
+        let bytes = postcard::to_allocvec(&jr).unwrap();
+        assert_eq!(bytes, vec![0, 1]);
+        let back: Envelope = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(back.0.0, Ok(1));
+    }
+}
@@ -0,0 +1,301 @@
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{self, DeserializeSeed, EnumAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Representation;
+
+/// Key names used by the [`External`] representation.
+///
+/// Implement this on a marker type to pick your own keys:
+///
+/// ```rust
+/// use json_result::repr::TagKeys;
+///
+/// struct SuccessFailure;
+///
+/// impl TagKeys for SuccessFailure {
+///     const OK: &'static str = "success";
+///     const ERR: &'static str = "failure";
+/// }
+/// ```
+pub trait TagKeys {
+    /// Key wrapping the `Ok` value.
+    const OK: &'static str;
+    /// Key wrapping the `Err` value.
+    const ERR: &'static str;
+
+    /// Both keys, in variant order.
+    const VARIANTS: &'static [&'static str] = &[Self::OK, Self::ERR];
+}
+
+/// Tags the branches as `{"ok": ...}` / `{"err": ...}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OkErr;
+
+impl TagKeys for OkErr {
+    const OK: &'static str = "ok";
+    const ERR: &'static str = "err";
+}
+
+/// Tags the branches as `{"ok": ...}` / `{"error": ...}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OkError;
+
+impl TagKeys for OkError {
+    const OK: &'static str = "ok";
+    const ERR: &'static str = "error";
+}
+
+/// External<K>
+///
+/// Externally tagged representation: the branch is wrapped in a single-key object whose key
+/// is `K::OK` or `K::ERR`, e.g. `{"ok": 1}` / `{"err": "boom"}`.
+///
+/// The branch is taken from the key, so a payload that would fit both `T` and `E` is never
+/// ambiguous.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct External<K = OkErr>(PhantomData<K>);
+
+const NAME: &str = "JsonResult";
+
+impl<K: TagKeys> Representation for External<K> {
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer,
+    {
+        match result {
+            Ok(v) => serializer.serialize_newtype_variant(NAME, 0, K::OK, v),
+            Err(e) => serializer.serialize_newtype_variant(NAME, 1, K::ERR, e),
+        }
+    }
+
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_enum(NAME, K::VARIANTS, ExternalVisitor::<T, E, K>(PhantomData))
+    }
+}
+
+enum Branch {
+    Ok,
+    Err,
+}
+
+struct BranchSeed<K>(PhantomData<K>);
+
+impl<'de, K: TagKeys> DeserializeSeed<'de> for BranchSeed<K> {
+    type Value = Branch;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_identifier(self)
+    }
+}
+
+impl<'de, K: TagKeys> Visitor<'de> for BranchSeed<K> {
+    type Value = Branch;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` or `{}`", K::OK, K::ERR)
+    }
+
+    fn visit_u64<Er: de::Error>(self, v: u64) -> Result<Self::Value, Er> {
+        match v {
+            0 => Ok(Branch::Ok),
+            1 => Ok(Branch::Err),
+            _ => Err(Er::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_str<Er: de::Error>(self, v: &str) -> Result<Self::Value, Er> {
+        if v == K::OK {
+            Ok(Branch::Ok)
+        } else if v == K::ERR {
+            Ok(Branch::Err)
+        } else {
+            Err(Er::unknown_variant(v, K::VARIANTS))
+        }
+    }
+
+    fn visit_bytes<Er: de::Error>(self, v: &[u8]) -> Result<Self::Value, Er> {
+        match core::str::from_utf8(v) {
+            Ok(s) => self.visit_str(s),
+            Err(_) => Err(Er::invalid_value(de::Unexpected::Bytes(v), &self)),
+        }
+    }
+}
+
+struct ExternalVisitor<T, E, K>(PhantomData<(T, E, K)>);
+
+impl<'de, T, E, K> Visitor<'de> for ExternalVisitor<T, E, K>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+    K: TagKeys,
+{
+    type Value = Result<T, E>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an object with a single `{}` or `{}` key", K::OK, K::ERR)
+    }
+
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        match data.variant_seed(BranchSeed::<K>(PhantomData))? {
+            (Branch::Ok, v) => v.newtype_variant().map(Ok),
+            (Branch::Err, v) => v.newtype_variant().map(Err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{External, OkError, TagKeys};
+    use crate::repr::test_support::{Amb, BadE, EnumRepr, GoodT, StructRepr};
+    use crate::{r#enum, r#struct};
+
+    struct SuccessFailure;
+
+    impl TagKeys for SuccessFailure {
+        const OK: &'static str = "success";
+        const ERR: &'static str = "failure";
+    }
+
+    #[test]
+    fn test_struct_ok_serialization() {
+        let jr = StructRepr::<i32, String, External>::new(r#struct::JsonResult(Ok(100)));
+        let s = serde_json::to_string(&jr).unwrap();
+        assert_eq!(s, r#"{"ok":100}"#);
+    }
+
+    #[test]
+    fn test_struct_err_serialization() {
+        let jr = StructRepr::<i32, &str, External<OkError>>::new(r#struct::JsonResult(Err("boom")));
+        let s = serde_json::to_string(&jr).unwrap();
+        assert_eq!(s, r#"{"error":"boom"}"#);
+    }
+
+    #[test]
+    fn test_struct_round_trip_ok() {
+        let original =
+            StructRepr::<GoodT, BadE, External>::new(r#struct::JsonResult(Ok(GoodT { x: 55 })));
+        let json = serde_json::to_value(&original).unwrap();
+        assert_eq!(json, serde_json::json!({ "ok": { "x": 55 } }));
+
+        let parsed: StructRepr<GoodT, BadE, External> = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.0.0, Ok(GoodT { x: 55 }));
+    }
+
+    #[test]
+    fn test_struct_round_trip_err() {
+        let original = StructRepr::<GoodT, BadE, External>::new(r#struct::JsonResult(Err(BadE {
+            msg: "x".into(),
+        })));
+        let json = serde_json::to_value(&original).unwrap();
+        assert_eq!(json, serde_json::json!({ "err": { "msg": "x" } }));
+
+        let parsed: StructRepr<GoodT, BadE, External> = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.0.0, Err(BadE { msg: "x".into() }));
+    }
+
+    #[test]
+    fn test_enum_round_trip_ok() {
+        let original: EnumRepr<GoodT, BadE, External> =
+            r#enum::JsonResult::Ok(GoodT { x: 42 }).into();
+
+        let json: serde_json::Value = original.into();
+        let parsed = EnumRepr::<GoodT, BadE, External>::try_from(json).unwrap();
+
+        match parsed.into_inner() {
+            r#enum::JsonResult::Ok(v) => assert_eq!(v.x, 42),
+            _ => panic!("Round trip for T failed"),
+        }
+    }
+
+    #[test]
+    fn test_enum_round_trip_err() {
+        let original: EnumRepr<GoodT, BadE, External<OkError>> =
+            r#enum::JsonResult::Err(BadE { msg: "boom".into() }).into();
+
+        let json: serde_json::Value = original.into();
+        assert_eq!(json, serde_json::json!({ "error": { "msg": "boom" } }));
+        let parsed = EnumRepr::<GoodT, BadE, External<OkError>>::try_from(json).unwrap();
+
+        match parsed.into_inner() {
+            r#enum::JsonResult::Err(v) => assert_eq!(v.msg, "boom"),
+            _ => panic!("Round trip for E failed"),
+        }
+    }
+
+    #[test]
+    fn test_ambiguous_payload_follows_tag() {
+        let json = serde_json::json!({ "err": { "x": 10 } });
+        let jr: StructRepr<Amb, Amb, External> = serde_json::from_value(json).unwrap();
+
+        // The key decides, not the order in which T and E are tried
+        assert_eq!(jr.0.0, Err(Amb { x: 10 }));
+    }
+
+    #[test]
+    fn test_custom_keys() {
+        let json = serde_json::json!({ "failure": { "msg": "nope" } });
+        let jr: EnumRepr<GoodT, BadE, External<SuccessFailure>> = json.try_into().unwrap();
+
+        assert!(matches!(jr.0, r#enum::JsonResult::Err(BadE { ref msg }) if msg == "nope"));
+
+        let back: serde_json::Value = jr.into();
+        assert_eq!(back, serde_json::json!({ "failure": { "msg": "nope" } }));
+    }
+
+    #[test]
+    fn test_unknown_key_fails() {
+        let json = serde_json::json!({ "maybe": { "x": 1 } });
+        let result = StructRepr::<GoodT, BadE, External>::try_from(json);
+
+        let msg = result.unwrap_err().to_string();
+        assert!(msg.contains("maybe"));
+        assert!(msg.contains("ok"));
+        assert!(msg.contains("err"));
+    }
+
+    #[test]
+    fn test_untagged_payload_fails() {
+        let json = serde_json::json!({ "x": 1 });
+        let result = StructRepr::<GoodT, BadE, External>::try_from(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_wrong_shape_under_tag_fails() {
+        let json = serde_json::json!({ "ok": { "msg": "not a GoodT" } });
+        let result = EnumRepr::<GoodT, BadE, External>::try_from(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_null_fails() {
+        let json = serde_json::json!(null);
+        let result = StructRepr::<GoodT, BadE, External>::try_from(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn deref_reaches_inner_result() {
+        let jr = StructRepr::<u32, &str, External>::new(r#struct::JsonResult(Ok(42)));
+
+        // Repr derefs to JsonResult, which derefs to Result
+        assert!(jr.is_ok());
+        assert_eq!(jr.as_ref().unwrap(), &42);
+    }
+}
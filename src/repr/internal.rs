@@ -0,0 +1,851 @@
+use alloc::format;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de;
+use serde::de::value::UnitDeserializer;
+use serde::ser::{self, Impossible, SerializeMap, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Representation;
+use crate::tape::{Tape, Token, skip};
+
+/// A discriminator value written next to the payload fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagValue {
+    /// A string value, e.g. `"type": "error"`.
+    Str(&'static str),
+    /// A boolean value, e.g. `"success": false`.
+    Bool(bool),
+    /// An integer value, e.g. `"ok": 1`.
+    Int(i64),
+}
+
+impl TagValue {
+    fn matches(&self, token: &Token<'_>) -> bool {
+        match (self, token) {
+            (TagValue::Str(s), Token::Str(v)) => s == v,
+            (TagValue::Bool(b), Token::Bool(v)) => b == v,
+            (TagValue::Int(i), Token::I64(v)) => i == v,
+            (TagValue::Int(i), Token::U64(v)) => i64::try_from(*v) == Ok(*i),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TagValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagValue::Str(s) => write!(f, "{:?}", s),
+            TagValue::Bool(b) => write!(f, "{}", b),
+            TagValue::Int(i) => write!(f, "{}", i),
+        }
+    }
+}
+
+impl Serialize for TagValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            TagValue::Str(s) => serializer.serialize_str(s),
+            TagValue::Bool(b) => serializer.serialize_bool(b),
+            TagValue::Int(i) => serializer.serialize_i64(i),
+        }
+    }
+}
+
+/// The discriminator field used by the [`Internal`] representation.
+///
+/// ```rust
+/// use json_result::repr::{Discriminator, TagValue};
+///
+/// /// `{"code": 0, ...}` / `{"code": 1, ...}`
+/// struct Code;
+///
+/// impl Discriminator for Code {
+///     const FIELD: &'static str = "code";
+///     const OK: TagValue = TagValue::Int(0);
+///     const ERR: TagValue = TagValue::Int(1);
+/// }
+/// ```
+pub trait Discriminator {
+    /// Name of the discriminator field.
+    const FIELD: &'static str;
+    /// Value marking the `Ok` branch.
+    const OK: TagValue;
+    /// Value marking the `Err` branch.
+    const ERR: TagValue;
+}
+
+/// `{"success": true, ...}` / `{"success": false, ...}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SuccessFlag;
+
+impl Discriminator for SuccessFlag {
+    const FIELD: &'static str = "success";
+    const OK: TagValue = TagValue::Bool(true);
+    const ERR: TagValue = TagValue::Bool(false);
+}
+
+/// `{"type": "success", ...}` / `{"type": "error", ...}`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TypeField;
+
+impl Discriminator for TypeField {
+    const FIELD: &'static str = "type";
+    const OK: TagValue = TagValue::Str("success");
+    const ERR: TagValue = TagValue::Str("error");
+}
+
+/// Internal<K>
+///
+/// Internally tagged representation: the discriminator `K::FIELD` sits next to the payload
+/// fields in a flat object, e.g. `{"success": false, "message": "boom"}`.
+///
+/// On deserialization the object is recorded once, the discriminator picks the branch, and
+/// the recording is replayed into `T` or `E` with the discriminator left out, so payloads
+/// read back whatever the format wrote, such as 128-bit integers, byte strings and
+/// non-string keys. Both `T` and `E` must serialize as objects (or as `null`,
+/// which writes the discriminator alone), and a payload field named like the discriminator
+/// is a serialization error rather than being dropped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Internal<K = SuccessFlag>(PhantomData<K>);
+
+impl<K: Discriminator> Representation for Internal<K> {
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        match result {
+            Ok(v) => {
+                map.serialize_entry(K::FIELD, &K::OK)?;
+                v.serialize(Flatten::<_, K>::new(&mut map))?;
+            }
+            Err(e) => {
+                map.serialize_entry(K::FIELD, &K::ERR)?;
+                e.serialize(Flatten::<_, K>::new(&mut map))?;
+            }
+        }
+        map.end()
+    }
+
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let tape = Tape::deserialize(deserializer)?;
+        let tokens = tape.tokens();
+        let Token::Map { len, end } = tokens[0] else {
+            return Err(de::Error::custom(format!(
+                "expected an object with a `{}` field, got {}",
+                K::FIELD,
+                kind(tokens, 0)
+            )));
+        };
+
+        // Find the discriminator among the object's own entries.
+        let mut tag = None;
+        let mut pos = 1;
+        while pos < end {
+            let value = skip(tokens, pos);
+            if matches!(&tokens[pos], Token::Str(key) if key == K::FIELD) {
+                if tag.is_some() {
+                    return Err(de::Error::duplicate_field(K::FIELD));
+                }
+                tag = Some((pos, value));
+            }
+            pos = skip(tokens, value);
+        }
+        let (key, mut value) = tag.ok_or_else(|| de::Error::missing_field(K::FIELD))?;
+        if let Token::Some = tokens[value] {
+            value += 1;
+        }
+
+        if K::OK.matches(&tokens[value]) {
+            from_fields(&tape, key, len).map(Ok)
+        } else if K::ERR.matches(&tokens[value]) {
+            from_fields(&tape, key, len).map(Err)
+        } else {
+            Err(de::Error::custom(format!(
+                "unknown `{}` value {}, expected {} or {}",
+                K::FIELD,
+                tape.to_value_at(value),
+                K::OK,
+                K::ERR
+            )))
+        }
+    }
+}
+
+/// Writes the fields of a payload into the enclosing map, next to the discriminator, without
+/// going through a `Value`, so the payload keeps whatever the target format supports.
+struct Flatten<'a, M, K> {
+    map: &'a mut M,
+    discriminator: PhantomData<K>,
+}
+
+impl<'a, M, K> Flatten<'a, M, K> {
+    fn new(map: &'a mut M) -> Self {
+        Flatten {
+            map,
+            discriminator: PhantomData,
+        }
+    }
+}
+
+impl<M: SerializeMap, K: Discriminator> Flatten<'_, M, K> {
+    fn not_an_object(kind: &str) -> M::Error {
+        ser::Error::custom(format!(
+            "internally tagged payload must serialize as an object, got {}",
+            kind
+        ))
+    }
+}
+
+fn collision<Er: ser::Error, K: Discriminator>() -> Er {
+    Er::custom(format!(
+        "payload field `{}` collides with discriminator",
+        K::FIELD
+    ))
+}
+
+impl<'a, M: SerializeMap, K: Discriminator> Serializer for Flatten<'a, M, K> {
+    type Ok = ();
+    type Error = M::Error;
+    type SerializeSeq = Impossible<(), M::Error>;
+    type SerializeTuple = Impossible<(), M::Error>;
+    type SerializeTupleStruct = Impossible<(), M::Error>;
+    type SerializeTupleVariant = Impossible<(), M::Error>;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), M::Error>;
+
+    fn serialize_bool(self, _: bool) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a boolean"))
+    }
+
+    fn serialize_i8(self, _: i8) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_i16(self, _: i16) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_i32(self, _: i32) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_i64(self, _: i64) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_i128(self, _: i128) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_u8(self, _: u8) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_u16(self, _: u16) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_u32(self, _: u32) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_u64(self, _: u64) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_u128(self, _: u128) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_f32(self, _: f32) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a number"))
+    }
+
+    fn serialize_char(self, _: char) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a string"))
+    }
+
+    fn serialize_str(self, _: &str) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a string"))
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), M::Error> {
+        Err(Self::not_an_object("bytes"))
+    }
+
+    /// `null` payloads write the discriminator alone.
+    fn serialize_none(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_some<V: ?Sized + Serialize>(self, value: &V) -> Result<(), M::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), M::Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<(), M::Error> {
+        Err(Self::not_an_object("a string"))
+    }
+
+    fn serialize_newtype_struct<V: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        value.serialize(self)
+    }
+
+    /// Written as `{"Variant": value}`, like serde's externally tagged enums.
+    fn serialize_newtype_variant<V: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        if variant == K::FIELD {
+            return Err(collision::<M::Error, K>());
+        }
+        self.map.serialize_entry(variant, value)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, M::Error> {
+        Err(Self::not_an_object("an array"))
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, M::Error> {
+        Err(Self::not_an_object("an array"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, M::Error> {
+        Err(Self::not_an_object("an array"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, M::Error> {
+        Err(Self::not_an_object("a tuple variant"))
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, M::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, M::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, M::Error> {
+        Err(Self::not_an_object("a struct variant"))
+    }
+}
+
+impl<M: SerializeMap, K: Discriminator> SerializeMap for Flatten<'_, M, K> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_key<V: ?Sized + Serialize>(&mut self, key: &V) -> Result<(), M::Error> {
+        if key.serialize(IsField(K::FIELD)).unwrap_or(false) {
+            return Err(collision::<M::Error, K>());
+        }
+        self.map.serialize_key(key)
+    }
+
+    fn serialize_value<V: ?Sized + Serialize>(&mut self, value: &V) -> Result<(), M::Error> {
+        self.map.serialize_value(value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+impl<M: SerializeMap, K: Discriminator> SerializeStruct for Flatten<'_, M, K> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<V: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &V,
+    ) -> Result<(), M::Error> {
+        if key == K::FIELD {
+            return Err(collision::<M::Error, K>());
+        }
+        self.map.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+/// Checks whether a map key is the string `self.0`; keys of any other type never are.
+struct IsField(&'static str);
+
+macro_rules! not_a_str {
+    ($($method:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $method(self, _: $ty) -> Result<bool, fmt::Error> {
+                Ok(false)
+            }
+        )*
+    };
+}
+
+impl Serializer for IsField {
+    type Ok = bool;
+    type Error = fmt::Error;
+    type SerializeSeq = Impossible<bool, fmt::Error>;
+    type SerializeTuple = Impossible<bool, fmt::Error>;
+    type SerializeTupleStruct = Impossible<bool, fmt::Error>;
+    type SerializeTupleVariant = Impossible<bool, fmt::Error>;
+    type SerializeMap = Impossible<bool, fmt::Error>;
+    type SerializeStruct = Impossible<bool, fmt::Error>;
+    type SerializeStructVariant = Impossible<bool, fmt::Error>;
+
+    not_a_str! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_bytes(&[u8]),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_char(self, c: char) -> Result<bool, fmt::Error> {
+        let mut buf = [0; 4];
+        Ok(c.encode_utf8(&mut buf) == self.0)
+    }
+
+    fn serialize_str(self, s: &str) -> Result<bool, fmt::Error> {
+        Ok(s == self.0)
+    }
+
+    fn serialize_none(self) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_some<V: ?Sized + Serialize>(self, value: &V) -> Result<bool, fmt::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<bool, fmt::Error> {
+        Ok(variant == self.0)
+    }
+
+    fn serialize_newtype_struct<V: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &V,
+    ) -> Result<bool, fmt::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<V: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &V,
+    ) -> Result<bool, fmt::Error> {
+        Ok(false)
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, fmt::Error> {
+        Err(fmt::Error)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, fmt::Error> {
+        Err(fmt::Error)
+    }
+}
+
+/// Replays the recorded object without the discriminator entry at `key` into `V`.
+fn from_fields<'de, V, Er>(tape: &Tape<'de>, key: usize, len: usize) -> Result<V, Er>
+where
+    V: Deserialize<'de>,
+    Er: de::Error,
+{
+    if len == 1 {
+        // Unit-like payloads were written as the discriminator alone.
+        if let Ok(v) = V::deserialize(UnitDeserializer::<Er>::new()) {
+            return Ok(v);
+        }
+    }
+    V::deserialize(tape.replay_without::<Er>(key))
+}
+
+fn kind(tokens: &[Token<'_>], pos: usize) -> &'static str {
+    match tokens[pos] {
+        Token::Null => "null",
+        Token::Bool(_) => "a boolean",
+        Token::U64(_) | Token::I64(_) | Token::I128(_) | Token::U128(_) | Token::F64(_) => {
+            "a number"
+        }
+        Token::Str(_) => "a string",
+        Token::Bytes(_) => "bytes",
+        Token::Some => kind(tokens, pos + 1),
+        Token::Seq { .. } => "an array",
+        Token::Map { .. } => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::{Discriminator, Internal, TagValue, TypeField};
+    use crate::repr::test_support::{Amb, BadE, EnumRepr, GoodT, StructRepr};
+    use crate::{r#enum, r#struct};
+
+    struct Code;
+
+    impl Discriminator for Code {
+        const FIELD: &'static str = "code";
+        const OK: TagValue = TagValue::Int(0);
+        const ERR: TagValue = TagValue::Int(1);
+    }
+
+    #[test]
+    fn test_ok_serialization() {
+        let jr = StructRepr::<GoodT, BadE, Internal>::new(r#struct::JsonResult(Ok(GoodT { x: 1 })));
+        let s = serde_json::to_string(&jr).unwrap();
+        assert_eq!(s, r#"{"success":true,"x":1}"#);
+    }
+
+    #[test]
+    fn test_err_serialization() {
+        let jr = StructRepr::<GoodT, BadE, Internal>::new(r#struct::JsonResult(Err(BadE {
+            msg: "boom".into(),
+        })));
+        let s = serde_json::to_string(&jr).unwrap();
+        assert_eq!(s, r#"{"success":false,"msg":"boom"}"#);
+    }
+
+    #[test]
+    fn test_struct_round_trip_ok() {
+        let original =
+            StructRepr::<GoodT, BadE, Internal>::new(r#struct::JsonResult(Ok(GoodT { x: 55 })));
+        let json = serde_json::to_value(&original).unwrap();
+        let parsed: StructRepr<GoodT, BadE, Internal> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(parsed.0.0, Ok(GoodT { x: 55 }));
+    }
+
+    #[test]
+    fn test_struct_round_trip_err() {
+        let original = StructRepr::<GoodT, BadE, Internal>::new(r#struct::JsonResult(Err(BadE {
+            msg: "x".into(),
+        })));
+        let json = serde_json::to_value(&original).unwrap();
+        let parsed: StructRepr<GoodT, BadE, Internal> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(parsed.0.0, Err(BadE { msg: "x".into() }));
+    }
+
+    #[test]
+    fn test_enum_round_trip_string_tag() {
+        let original: EnumRepr<GoodT, BadE, Internal<TypeField>> =
+            r#enum::JsonResult::Err(BadE { msg: "boom".into() }).into();
+
+        let json: serde_json::Value = original.into();
+        assert_eq!(json, serde_json::json!({ "type": "error", "msg": "boom" }));
+
+        let parsed = EnumRepr::<GoodT, BadE, Internal<TypeField>>::try_from(json).unwrap();
+        match parsed.into_inner() {
+            r#enum::JsonResult::Err(e) => assert_eq!(e.msg, "boom"),
+            _ => panic!("Round trip for E failed"),
+        }
+    }
+
+    #[test]
+    fn test_integer_tag() {
+        let json = serde_json::json!({ "code": 0, "x": 3 });
+        let jr = EnumRepr::<GoodT, BadE, Internal<Code>>::try_from(json).unwrap();
+        assert!(matches!(jr.0, r#enum::JsonResult::Ok(GoodT { x: 3 })));
+
+        let json = serde_json::json!({ "code": 1, "msg": "nope" });
+        let jr = EnumRepr::<GoodT, BadE, Internal<Code>>::try_from(json).unwrap();
+        assert!(matches!(jr.0, r#enum::JsonResult::Err(_)));
+    }
+
+    #[test]
+    fn test_ambiguous_payload_follows_tag() {
+        let json = serde_json::json!({ "success": false, "x": 10 });
+        let jr: StructRepr<Amb, Amb, Internal> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(jr.0.0, Err(Amb { x: 10 }));
+    }
+
+    #[test]
+    fn test_unit_payload() {
+        let original = StructRepr::<(), BadE, Internal>::new(r#struct::JsonResult(Ok(())));
+        let json = serde_json::to_value(&original).unwrap();
+        assert_eq!(json, serde_json::json!({ "success": true }));
+
+        let parsed = StructRepr::<(), BadE, Internal>::try_from(json).unwrap();
+        assert_eq!(parsed.0.0, Ok(()));
+    }
+
+    #[test]
+    fn test_non_object_payload_fails_to_serialize() {
+        let jr = StructRepr::<i32, BadE, Internal>::new(r#struct::JsonResult(Ok(5)));
+        assert!(serde_json::to_string(&jr).is_err());
+    }
+
+    #[test]
+    fn test_missing_tag_fails() {
+        let json = serde_json::json!({ "x": 1 });
+        let msg = StructRepr::<GoodT, BadE, Internal>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("missing field `success`"));
+    }
+
+    #[test]
+    fn test_unknown_tag_fails() {
+        let json = serde_json::json!({ "type": "warning", "x": 1 });
+        let msg = StructRepr::<GoodT, BadE, Internal<TypeField>>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("unknown `type` value \"warning\""));
+    }
+
+    #[test]
+    fn test_tag_of_wrong_type_fails() {
+        let json = serde_json::json!({ "success": "yes", "x": 1 });
+        let result = StructRepr::<GoodT, BadE, Internal>::try_from(json);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_payload_field_colliding_with_discriminator_fails() {
+        #[derive(serde::Serialize)]
+        struct Flagged {
+            success: bool,
+            x: u32,
+        }
+
+        let jr = StructRepr::<Flagged, BadE, Internal>::new(r#struct::JsonResult(Ok(Flagged {
+            success: false,
+            x: 1,
+        })));
+        let msg = serde_json::to_string(&jr).unwrap_err().to_string();
+        assert!(msg.contains("payload field `success` collides with discriminator"));
+
+        let map = std::collections::BTreeMap::from([("type", 1), ("x", 2)]);
+        let jr = StructRepr::<_, BadE, Internal<TypeField>>::new(r#struct::JsonResult(Ok(map)));
+        let msg = serde_json::to_string(&jr).unwrap_err().to_string();
+        assert!(msg.contains("payload field `type` collides with discriminator"));
+    }
+
+    #[test]
+    fn test_serialization_does_not_go_through_value() {
+        #[derive(serde::Serialize)]
+        struct Big {
+            id: u128,
+            tags: Option<Vec<&'static str>>,
+        }
+
+        let jr = StructRepr::<Big, BadE, Internal<Code>>::new(r#struct::JsonResult(Ok(Big {
+            id: u128::MAX,
+            tags: None,
+        })));
+
+        assert_eq!(
+            serde_json::to_string(&jr).unwrap(),
+            format!(r#"{{"code":0,"id":{},"tags":null}}"#, u128::MAX)
+        );
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_round_trip_keeps_values_a_value_cannot_hold() {
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, serde::Serialize, Deserialize, PartialEq)]
+        struct Big {
+            id: u128,
+            tags: Option<Vec<String>>,
+            by_id: BTreeMap<u32, String>,
+        }
+
+        let original =
+            StructRepr::<Big, BadE, Internal<Code>>::new(r#struct::JsonResult(Ok(Big {
+                id: u128::MAX,
+                tags: None,
+                by_id: BTreeMap::from([(7, "seven".to_string())]),
+            })));
+        let mut buf = Vec::new();
+        ciborium::into_writer(&original, &mut buf).unwrap();
+
+        let parsed: StructRepr<Big, BadE, Internal<Code>> =
+            ciborium::from_reader(buf.as_slice()).unwrap();
+        assert_eq!(parsed.0.0, original.0.0);
+    }
+
+    #[test]
+    fn test_tag_after_payload_fields() {
+        let json = r#"{"x": 4, "code": 0}"#;
+        let jr: StructRepr<GoodT, BadE, Internal<Code>> = serde_json::from_str(json).unwrap();
+        assert_eq!(jr.0.0, Ok(GoodT { x: 4 }));
+
+        let json = r#"{"x": "four", "code": 0}"#;
+        let msg = serde_json::from_str::<StructRepr<GoodT, BadE, Internal<Code>>>(json)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(msg, r#"invalid type: string "four", expected u32"#);
+    }
+
+    #[test]
+    fn test_borrows_from_input() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Note<'a> {
+            text: &'a str,
+        }
+
+        let input = r#"{"text": "kept", "type": "error"}"#;
+        let jr: StructRepr<GoodT, Note<'_>, Internal<TypeField>> =
+            serde_json::from_str(input).unwrap();
+        assert_eq!(jr.0.0, Err(Note { text: "kept" }));
+    }
+
+    #[test]
+    fn test_duplicate_tag_fails() {
+        let json = r#"{"success": true, "x": 1, "success": false}"#;
+        let msg = serde_json::from_str::<StructRepr<GoodT, BadE, Internal>>(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("duplicate field `success`"));
+    }
+
+    #[test]
+    fn test_unit_and_non_object_payloads() {
+        let jr = StructRepr::<(), BadE, Internal>::new(r#struct::JsonResult(Ok(())));
+        assert_eq!(serde_json::to_string(&jr).unwrap(), r#"{"success":true}"#);
+
+        let jr = StructRepr::<Vec<u8>, BadE, Internal>::new(r#struct::JsonResult(Ok(vec![1])));
+        let msg = serde_json::to_string(&jr).unwrap_err().to_string();
+        assert!(msg.contains("must serialize as an object, got an array"));
+    }
+
+    #[test]
+    fn test_non_object_fails() {
+        let json = serde_json::json!([1, 2, 3]);
+        let msg = EnumRepr::<GoodT, BadE, Internal>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("got an array"));
+    }
+}
@@ -0,0 +1,281 @@
+use alloc::format;
+use core::marker::PhantomData;
+
+use serde::de;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Representation;
+use crate::tape::{Tape, Token, skip};
+
+/// Keys whose presence marks a payload as an error, used by the [`Presence`] representation.
+///
+/// ```rust
+/// use json_result::repr::PresenceKeys;
+///
+/// struct Fault;
+///
+/// impl PresenceKeys for Fault {
+///     const KEYS: &'static [&'static str] = &["fault", "faultString"];
+/// }
+/// ```
+pub trait PresenceKeys {
+    /// If any of these keys is present with a non-null value, the payload is an `Err`.
+    const KEYS: &'static [&'static str];
+}
+
+/// Treats objects with an `error` or `errors` key as `Err`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ErrorField;
+
+impl PresenceKeys for ErrorField {
+    const KEYS: &'static [&'static str] = &["error", "errors"];
+}
+
+/// Presence<K>
+///
+/// Untagged on the wire, but the branch is chosen by looking for the keys in `K::KEYS`
+/// instead of trying `T` first: an object holding any of them is deserialized as `E`,
+/// anything else as `T`.
+///
+/// A key whose value is `null` counts as absent, since many APIs send `"error": null` on
+/// success.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Presence<K = ErrorField>(PhantomData<K>);
+
+impl<K: PresenceKeys> Representation for Presence<K> {
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer,
+    {
+        match result {
+            Ok(v) => v.serialize(serializer),
+            Err(e) => e.serialize(serializer),
+        }
+    }
+
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let tape = Tape::deserialize(deserializer)?;
+
+        match present::<K>(tape.tokens()) {
+            Some(key) => E::deserialize(tape.replay_as::<D::Error>())
+                .map(Err)
+                .map_err(|e| {
+                    de::Error::custom(format!(
+                        "`{}` is present but failed to parse as {}: {}",
+                        key,
+                        core::any::type_name::<E>(),
+                        e
+                    ))
+                }),
+            None => T::deserialize(tape.replay_as::<D::Error>())
+                .map(Ok)
+                .map_err(|e| {
+                    de::Error::custom(format!(
+                        "none of {:?} is present but failed to parse as {}: {}",
+                        K::KEYS,
+                        core::any::type_name::<T>(),
+                        e
+                    ))
+                }),
+        }
+    }
+}
+
+/// The first of `K::KEYS` that the recorded object holds with a non-null value.
+fn present<K: PresenceKeys>(tokens: &[Token<'_>]) -> Option<&'static str> {
+    let Token::Map { end, .. } = tokens[0] else {
+        return None;
+    };
+
+    K::KEYS.iter().copied().find(|field| {
+        let mut pos = 1;
+        while pos < end {
+            let value = skip(tokens, pos);
+            if matches!(&tokens[pos], Token::Str(key) if key == field)
+                && !matches!(tokens[value], Token::Null)
+            {
+                return true;
+            }
+            pos = skip(tokens, value);
+        }
+        false
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::{Presence, PresenceKeys};
+    use crate::repr::test_support::{EnumRepr, StructRepr};
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Page {
+        #[serde(default)]
+        items: Vec<u32>,
+        next: Option<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct ApiError {
+        error: String,
+    }
+
+    struct Fault;
+
+    impl PresenceKeys for Fault {
+        const KEYS: &'static [&'static str] = &["fault"];
+    }
+
+    #[test]
+    fn test_ok_serialization_is_untagged() {
+        let jr = StructRepr::<i32, String, Presence>::new(r#struct::JsonResult(Ok(100)));
+        assert_eq!(serde_json::to_string(&jr).unwrap(), "100");
+    }
+
+    #[test]
+    fn test_err_serialization_is_untagged() {
+        let jr = StructRepr::<i32, ApiError, Presence>::new(r#struct::JsonResult(Err(ApiError {
+            error: "boom".into(),
+        })));
+        assert_eq!(serde_json::to_string(&jr).unwrap(), r#"{"error":"boom"}"#);
+    }
+
+    #[test]
+    fn test_error_body_that_also_fits_t_is_err() {
+        // With try-T-first this would silently become Ok(Page { items: [], next: None })
+        let json = serde_json::json!({ "error": "rate limited" });
+        let jr: StructRepr<Page, ApiError, Presence> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(
+            jr.0.0,
+            Err(ApiError {
+                error: "rate limited".into()
+            })
+        );
+    }
+
+    #[test]
+    fn test_success_body_is_ok() {
+        let json = serde_json::json!({ "items": [1, 2], "next": "abc" });
+        let jr = EnumRepr::<Page, ApiError, Presence>::try_from(json).unwrap();
+
+        match jr.into_inner() {
+            r#enum::JsonResult::Ok(p) => assert_eq!(p.items, vec![1, 2]),
+            _ => panic!("Expected Ok"),
+        }
+    }
+
+    #[test]
+    fn test_null_error_key_counts_as_absent() {
+        let json = serde_json::json!({ "items": [3], "error": null });
+        let jr = EnumRepr::<Page, ApiError, Presence>::try_from(json).unwrap();
+
+        assert!(matches!(jr.0, r#enum::JsonResult::Ok(_)));
+    }
+
+    #[test]
+    fn test_any_configured_key_selects_err() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Errors {
+            errors: Vec<String>,
+        }
+
+        let json = serde_json::json!({ "errors": ["a", "b"] });
+        let jr = StructRepr::<Page, Errors, Presence>::try_from(json).unwrap();
+
+        assert_eq!(
+            jr.0.0,
+            Err(Errors {
+                errors: vec!["a".into(), "b".into()]
+            })
+        );
+    }
+
+    #[test]
+    fn test_custom_keys() {
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct FaultE {
+            fault: u32,
+        }
+
+        let json = serde_json::json!({ "fault": 7 });
+        let jr = StructRepr::<Page, FaultE, Presence<Fault>>::try_from(json).unwrap();
+        assert_eq!(jr.0.0, Err(FaultE { fault: 7 }));
+
+        // `error` is not a configured key here
+        let json = serde_json::json!({ "error": "x" });
+        let jr = StructRepr::<Page, FaultE, Presence<Fault>>::try_from(json).unwrap();
+        assert!(jr.0.0.is_ok());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let original: EnumRepr<Page, ApiError, Presence> = r#enum::JsonResult::Err(ApiError {
+            error: "boom".into(),
+        })
+        .into();
+
+        let json: serde_json::Value = original.into();
+        let parsed = EnumRepr::<Page, ApiError, Presence>::try_from(json).unwrap();
+
+        match parsed.into_inner() {
+            r#enum::JsonResult::Err(e) => assert_eq!(e.error, "boom"),
+            _ => panic!("Round trip for E failed"),
+        }
+    }
+
+    #[test]
+    fn test_err_branch_failure_names_key() {
+        let json = serde_json::json!({ "error": { "code": 1 } });
+        let msg = StructRepr::<Page, ApiError, Presence>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("`error` is present"));
+        assert!(msg.contains("ApiError"));
+    }
+
+    #[test]
+    fn test_ok_branch_failure_is_not_retried_as_err() {
+        let json = serde_json::json!([1, 2, 3]);
+        let msg = StructRepr::<Page, ApiError, Presence>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("Page"));
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_round_trip_keeps_wide_values() {
+        use std::collections::BTreeMap;
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Quota {
+            error: String,
+            limit: u128,
+            used: BTreeMap<u16, u64>,
+        }
+
+        let original = StructRepr::<Page, Quota, Presence>::new(r#struct::JsonResult(Err(Quota {
+            error: "over quota".into(),
+            limit: u128::MAX,
+            used: BTreeMap::from([(443, 12)]),
+        })));
+        let mut buf = Vec::new();
+        ciborium::into_writer(&original, &mut buf).unwrap();
+
+        let parsed: StructRepr<Page, Quota, Presence> =
+            ciborium::from_reader(buf.as_slice()).unwrap();
+        assert_eq!(parsed.0.0, original.0.0);
+    }
+}
@@ -0,0 +1,853 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use serde_path_to_error::Segment;
+
+use super::Representation;
+use crate::diagnose::diagnose;
+use crate::error::{AmbiguousPayload, BranchError, DecodeError, JsonResultError};
+use crate::tape::Tape;
+use crate::validate::{Failed, Validate, Violations};
+
+/// What to do when a payload parses as both `T` and `E`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ambiguity {
+    /// Take the `Ok` branch. `E` is only tried when `T` fails.
+    PreferOk,
+    /// Take the `Err` branch. `T` is only tried when `E` fails.
+    PreferErr,
+    /// Fail with [`DecodeError::Ambiguous`]. Both branches are always tried.
+    Reject,
+    /// Take the branch that ignored fewer of the payload's fields, settling a tie with the
+    /// given rule. Both branches are always tried.
+    BestFit(Tie),
+}
+
+/// How [`Ambiguity::BestFit`] settles a payload that both branches fit equally well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tie {
+    /// Take the `Ok` branch.
+    PreferOk,
+    /// Take the `Err` branch.
+    PreferErr,
+    /// Fail with [`DecodeError::Ambiguous`].
+    Reject,
+}
+
+/// Selects an [`Ambiguity`] rule at the type level for [`Untagged`].
+pub trait AmbiguityPolicy {
+    /// The rule applied when both branches parse.
+    const ON_AMBIGUITY: Ambiguity;
+}
+
+/// `Ok` wins when both branches parse. This is the behaviour of the plain `JsonResult` types.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreferOk;
+
+impl AmbiguityPolicy for PreferOk {
+    const ON_AMBIGUITY: Ambiguity = Ambiguity::PreferOk;
+}
+
+/// `Err` wins when both branches parse.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreferErr;
+
+impl AmbiguityPolicy for PreferErr {
+    const ON_AMBIGUITY: Ambiguity = Ambiguity::PreferErr;
+}
+
+/// A payload that parses as both branches is an error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Strict;
+
+impl AmbiguityPolicy for Strict {
+    const ON_AMBIGUITY: Ambiguity = Ambiguity::Reject;
+}
+
+/// The branch that consumed more of the payload wins when both parse, i.e. the one whose
+/// `Deserialize` impl skipped fewer unknown fields. A tie is settled by the policy `P`.
+///
+/// Useful for loosely typed APIs whose success and error bodies overlap, e.g. both carry
+/// `id`, but only one of them also has `message` and `code`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BestFit<P = PreferOk>(PhantomData<P>);
+
+impl<P: AmbiguityPolicy> AmbiguityPolicy for BestFit<P> {
+    const ON_AMBIGUITY: Ambiguity = Ambiguity::BestFit(match P::ON_AMBIGUITY {
+        Ambiguity::PreferOk => Tie::PreferOk,
+        Ambiguity::PreferErr => Tie::PreferErr,
+        Ambiguity::Reject => Tie::Reject,
+        Ambiguity::BestFit(tie) => tie,
+    });
+}
+
+/// Untagged<P>
+///
+/// The untagged representation used by the plain `JsonResult` types, with the handling of
+/// payloads that fit both `T` and `E` chosen by the policy `P`.
+///
+/// # Examples
+///
+/// ```rust
+/// use json_result::r#struct::JsonResult;
+/// use json_result::repr::{Repr, Strict, Untagged};
+///
+/// #[derive(Debug, serde::Deserialize)]
+/// struct Amount {
+///     value: u64,
+/// }
+///
+/// type Payment = Repr<JsonResult<Amount, Amount>, Untagged<Strict>>;
+///
+/// let err = serde_json::from_str::<Payment>(r#"{"value":1}"#).unwrap_err();
+/// assert!(err.to_string().contains("Ambiguous payload"));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Untagged<P = PreferOk>(PhantomData<P>);
+
+impl<P: AmbiguityPolicy> Untagged<P> {
+    /// Decodes `value` into either branch, keeping the failure typed.
+    pub fn from_value<T, E>(value: Value) -> Result<Result<T, E>, DecodeError>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+    {
+        decode(&value, P::ON_AMBIGUITY)
+    }
+
+    /// Parses `s` and decodes it into either branch.
+    ///
+    /// Input that is not valid JSON fails with [`DecodeError::Json`]; valid JSON that fits
+    /// neither branch fails with [`DecodeError::NoMatch`].
+    ///
+    /// Both branches may borrow from `s`, e.g. `&'de str` or `#[serde(borrow)]` fields.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str<'de, T, E>(s: &'de str) -> Result<Result<T, E>, DecodeError>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+    {
+        Untagged::<P>::from_slice(s.as_bytes())
+    }
+
+    /// Like [`Untagged::from_str`], for bytes.
+    pub fn from_slice<'de, T, E>(v: &'de [u8]) -> Result<Result<T, E>, DecodeError>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+    {
+        decode(record(v).map_err(DecodeError::Json)?, P::ON_AMBIGUITY)
+    }
+}
+
+impl<P: AmbiguityPolicy> Representation for Untagged<P> {
+    fn serialize<T, E, S>(result: Result<&T, &E>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        E: Serialize,
+        S: Serializer,
+    {
+        match result {
+            Ok(v) => v.serialize(serializer),
+            Err(e) => e.serialize(serializer),
+        }
+    }
+
+    fn deserialize<'de, T, E, D>(deserializer: D) -> Result<Result<T, E>, D::Error>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let tape = Tape::deserialize(deserializer)?;
+        decode(tape, P::ON_AMBIGUITY).map_err(de::Error::custom)
+    }
+}
+
+/// Records a whole JSON document, rejecting trailing characters like `serde_json::from_slice`.
+pub(crate) fn record(text: &[u8]) -> Result<Sourced<'_>, serde_json::Error> {
+    let mut de = serde_json::Deserializer::from_slice(text);
+    let tape = Tape::deserialize(&mut de)?;
+    de.end()?;
+    Ok(Sourced { tape, text })
+}
+
+/// A recorded document and the JSON text it came from. Branches are tried against the tape;
+/// a failed branch is re-read from the text, so its error carries a line and column.
+pub(crate) struct Sourced<'de> {
+    tape: Tape<'de>,
+    text: &'de [u8],
+}
+
+/// Input buffered so that both branches can be tried against it.
+pub(crate) trait Buffered<'de> {
+    /// Decodes one branch without consuming the buffer.
+    fn attempt<V: Deserialize<'de>>(&self) -> Result<V, serde_json::Error>;
+
+    /// Like [`Buffered::attempt`], also recording the unknown keys the branch skipped.
+    fn attempt_tracked<V: Deserialize<'de>>(
+        &self,
+        ignored: &mut Vec<String>,
+    ) -> Result<V, serde_json::Error>;
+
+    /// The buffered input as a [`Value`], for [`AmbiguousPayload`].
+    fn into_value(self) -> Value;
+
+    /// The buffered input as a [`Tape`], for replaying failures.
+    fn into_tape(self) -> Tape<'de>;
+
+    /// The JSON text the input was recorded from, when there is one.
+    fn text(&self) -> Option<&'de [u8]> {
+        None
+    }
+}
+
+/// `&Value` is itself a deserializer, so each attempt borrows the payload instead of cloning it.
+impl<'de> Buffered<'de> for &'de Value {
+    fn attempt<V: Deserialize<'de>>(&self) -> Result<V, serde_json::Error> {
+        V::deserialize(*self)
+    }
+
+    fn attempt_tracked<V: Deserialize<'de>>(
+        &self,
+        ignored: &mut Vec<String>,
+    ) -> Result<V, serde_json::Error> {
+        tracked(*self, ignored)
+    }
+
+    fn into_value(self) -> Value {
+        self.clone()
+    }
+
+    fn into_tape(self) -> Tape<'de> {
+        Tape::from(self)
+    }
+}
+
+/// Replays the recorded tokens; a [`Value`] is only built for an ambiguous payload.
+impl<'de> Buffered<'de> for Tape<'de> {
+    fn attempt<V: Deserialize<'de>>(&self) -> Result<V, serde_json::Error> {
+        V::deserialize(self.replay())
+    }
+
+    fn attempt_tracked<V: Deserialize<'de>>(
+        &self,
+        ignored: &mut Vec<String>,
+    ) -> Result<V, serde_json::Error> {
+        tracked(self.replay(), ignored)
+    }
+
+    fn into_value(self) -> Value {
+        self.to_value()
+    }
+
+    fn into_tape(self) -> Tape<'de> {
+        self
+    }
+}
+
+impl<'de> Buffered<'de> for Sourced<'de> {
+    fn attempt<V: Deserialize<'de>>(&self) -> Result<V, serde_json::Error> {
+        self.tape.attempt()
+    }
+
+    fn attempt_tracked<V: Deserialize<'de>>(
+        &self,
+        ignored: &mut Vec<String>,
+    ) -> Result<V, serde_json::Error> {
+        self.tape.attempt_tracked(ignored)
+    }
+
+    fn into_value(self) -> Value {
+        self.tape.to_value()
+    }
+
+    fn into_tape(self) -> Tape<'de> {
+        self.tape
+    }
+
+    fn text(&self) -> Option<&'de [u8]> {
+        Some(self.text)
+    }
+}
+
+/// Borrowing the tape lets the caller keep the input after a failure; it is only cloned then.
+impl<'de> Buffered<'de> for &Tape<'de> {
+    fn attempt<V: Deserialize<'de>>(&self) -> Result<V, serde_json::Error> {
+        V::deserialize(self.replay())
+    }
+
+    fn attempt_tracked<V: Deserialize<'de>>(
+        &self,
+        ignored: &mut Vec<String>,
+    ) -> Result<V, serde_json::Error> {
+        tracked(self.replay(), ignored)
+    }
+
+    fn into_value(self) -> Value {
+        self.to_value()
+    }
+
+    fn into_tape(self) -> Tape<'de> {
+        self.clone()
+    }
+}
+
+/// Deserializes `V`, recording every unknown key it skipped, at any depth, as a path like
+/// `.data.items[0].note`.
+fn tracked<'de, D, V>(deserializer: D, ignored: &mut Vec<String>) -> Result<V, D::Error>
+where
+    D: Deserializer<'de>,
+    V: Deserialize<'de>,
+{
+    serde_ignored::deserialize(deserializer, |path| {
+        let mut rendered = String::new();
+        render(&path, &mut rendered);
+        ignored.push(rendered);
+    })
+}
+
+/// Writes `path` in the same style as [`BranchError::path`].
+fn render(path: &serde_ignored::Path<'_>, out: &mut String) {
+    use serde_ignored::Path;
+
+    match path {
+        Path::Root => {}
+        Path::Seq { parent, index } => {
+            render(parent, out);
+            out.push('[');
+            out.push_str(&index.to_string());
+            out.push(']');
+        }
+        Path::Map { parent, key } => {
+            render(parent, out);
+            out.push('.');
+            out.push_str(key);
+        }
+        Path::Some { parent }
+        | Path::NewtypeStruct { parent }
+        | Path::NewtypeVariant { parent } => render(parent, out),
+    }
+}
+
+/// A successful attempt and the unknown keys it skipped, which are only recorded when tracked.
+type Attempt<V> = Result<(V, Vec<String>), serde_json::Error>;
+
+/// Checks a decoded `T` before it is accepted; see [`decode_tracked`].
+pub(crate) type Check<T> = fn(&T) -> Result<(), Violations>;
+
+/// Why the `T` branch was not taken.
+enum Rejected {
+    /// It did not parse.
+    Parse(serde_json::Error),
+    /// It parsed, but the check found these violations.
+    Invalid(Violations),
+}
+
+/// Runs `check` on a successful attempt, turning violations into a failure.
+fn checked<V>(attempt: Attempt<V>, check: Check<V>) -> Result<(V, Vec<String>), Rejected> {
+    let (value, ignored) = attempt.map_err(Rejected::Parse)?;
+    match check(&value) {
+        Ok(()) => Ok((value, ignored)),
+        Err(violations) => Err(Rejected::Invalid(violations)),
+    }
+}
+
+fn attempt<'de, V, B>(input: &B, track: bool) -> Attempt<V>
+where
+    V: Deserialize<'de>,
+    B: Buffered<'de>,
+{
+    if track {
+        let mut ignored = Vec::new();
+        input.attempt_tracked(&mut ignored).map(|v| (v, ignored))
+    } else {
+        input.attempt().map(|v| (v, Vec::new()))
+    }
+}
+
+/// Tries `T` and `E` against `input`, resolving a double match with `ambiguity`.
+pub(crate) fn decode<'de, T, E, B>(
+    input: B,
+    ambiguity: Ambiguity,
+) -> Result<Result<T, E>, DecodeError>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+    B: Buffered<'de>,
+{
+    decode_tracked(input, ambiguity, false, |_| Ok(())).map(|(result, _)| result)
+}
+
+/// Like [`decode`], running [`Validate::validate`] on a decoded `T`. A `T` that fails it is
+/// treated as a failed parse, and its violations are kept on the `T` branch error.
+pub(crate) fn decode_validated<'de, T, E, B>(
+    input: B,
+    ambiguity: Ambiguity,
+) -> Result<Result<T, E>, DecodeError>
+where
+    T: Deserialize<'de> + Validate,
+    E: Deserialize<'de>,
+    B: Buffered<'de>,
+{
+    decode_tracked(input, ambiguity, false, T::validate).map(|(result, _)| result)
+}
+
+/// Like [`decode`], also returning the unknown keys the chosen branch skipped when `track` is
+/// set. [`Ambiguity::BestFit`] always tracks them, since it compares the two branches by them.
+///
+/// A decoded `T` is only accepted once `check` passes it; otherwise `T` counts as failed.
+pub(crate) fn decode_tracked<'de, T, E, B>(
+    input: B,
+    ambiguity: Ambiguity,
+    track: bool,
+    check: Check<T>,
+) -> Result<(Result<T, E>, Vec<String>), DecodeError>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+    B: Buffered<'de>,
+{
+    let track = track || matches!(ambiguity, Ambiguity::BestFit(_));
+    let (try_t, try_e) = match ambiguity {
+        Ambiguity::PreferOk => {
+            let try_t = checked(attempt::<T, B>(&input, track), check);
+            if let Ok((v, ignored)) = try_t {
+                return Ok((Ok(v), ignored));
+            }
+            (try_t, attempt::<E, B>(&input, track))
+        }
+        Ambiguity::PreferErr => {
+            let try_e = attempt::<E, B>(&input, track);
+            if let Ok((e, ignored)) = try_e {
+                return Ok((Err(e), ignored));
+            }
+            (checked(attempt::<T, B>(&input, track), check), try_e)
+        }
+        Ambiguity::Reject => {
+            let try_t = checked(attempt::<T, B>(&input, track), check);
+            let try_e = attempt::<E, B>(&input, track);
+            if try_t.is_ok() && try_e.is_ok() {
+                return Err(DecodeError::Ambiguous(AmbiguousPayload::new::<T, E>(
+                    input.into_value(),
+                )));
+            }
+            (try_t, try_e)
+        }
+        Ambiguity::BestFit(tie) => {
+            match (
+                checked(attempt::<T, B>(&input, track), check),
+                attempt::<E, B>(&input, track),
+            ) {
+                (Ok((v, t_ignored)), Ok((e, e_ignored))) => {
+                    let prefer_ok = match t_ignored.len().cmp(&e_ignored.len()) {
+                        Ordering::Less => true,
+                        Ordering::Greater => false,
+                        Ordering::Equal => match tie {
+                            Tie::PreferOk => true,
+                            Tie::PreferErr => false,
+                            Tie::Reject => {
+                                return Err(DecodeError::Ambiguous(AmbiguousPayload::new::<T, E>(
+                                    input.into_value(),
+                                )));
+                            }
+                        },
+                    };
+                    return Ok(if prefer_ok {
+                        (Ok(v), t_ignored)
+                    } else {
+                        (Err(e), e_ignored)
+                    });
+                }
+                tries => tries,
+            }
+        }
+    };
+
+    match (try_t, try_e) {
+        (Ok((v, ignored)), _) => Ok((Ok(v), ignored)),
+        (_, Ok((e, ignored))) => Ok((Err(e), ignored)),
+        (Err(t_err), Err(e_err)) => {
+            let text = input.text();
+            let tape = input.into_tape();
+            let ok = match t_err {
+                Rejected::Parse(error) => locate::<T>(&tape, text, error),
+                Rejected::Invalid(violations) => {
+                    let error = de::Error::custom(Failed(&violations));
+                    BranchError::new::<T>(error, ".".to_string()).with_violations(violations)
+                }
+            };
+            Err(DecodeError::NoMatch(JsonResultError::new(
+                ok,
+                locate::<E>(&tape, text, e_err),
+            )))
+        }
+    }
+}
+
+/// Replays a failed attempt with path tracking to find where it went wrong, then diagnoses
+/// which fields were missing or unexpected there.
+///
+/// Tracking is only paid for on the failure path; the first attempt runs untracked. When the
+/// JSON text is at hand the replay reads it instead of the tape, so the error has a position.
+fn locate<'de, V: Deserialize<'de>>(
+    tape: &Tape<'de>,
+    text: Option<&'de [u8]>,
+    error: serde_json::Error,
+) -> BranchError {
+    let replayed = match text {
+        Some(text) => serde_path_to_error::deserialize::<_, V>(
+            &mut serde_json::Deserializer::from_slice(text),
+        ),
+        None => serde_path_to_error::deserialize::<_, V>(tape.replay()),
+    };
+    let (located, path) = match replayed {
+        Err(e) => {
+            let path: Vec<Segment> = e.path().iter().cloned().collect();
+            (BranchError::located::<V>(e), path)
+        }
+        Ok(_) => (BranchError::new::<V>(error, ".".to_string()), Vec::new()),
+    };
+    located.with_diagnosis(diagnose::<V>(tape, &path))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+    use serde_json::error::Category;
+
+    use super::{BestFit, PreferErr, PreferOk, Strict, Untagged};
+    use crate::classify::Branch;
+    use crate::error::DecodeError;
+    use crate::repr::test_support::{Amb, BadE, EnumRepr, GoodT, StructRepr};
+    use crate::{r#enum, r#struct};
+
+    #[test]
+    fn test_prefer_ok_matches_plain_json_result() {
+        let json = serde_json::json!({ "x": 10 });
+        let jr: StructRepr<Amb, Amb, Untagged<PreferOk>> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(jr.0.0, Ok(Amb { x: 10 }));
+    }
+
+    #[test]
+    fn test_prefer_err_wins_on_ambiguity() {
+        let json = serde_json::json!({ "x": 10 });
+        let jr: StructRepr<Amb, Amb, Untagged<PreferErr>> = serde_json::from_value(json).unwrap();
+
+        assert_eq!(jr.0.0, Err(Amb { x: 10 }));
+    }
+
+    #[test]
+    fn test_prefer_err_falls_back_to_ok() {
+        let json = serde_json::json!({ "x": 10 });
+        let jr = EnumRepr::<GoodT, BadE, Untagged<PreferErr>>::try_from(json).unwrap();
+
+        assert!(matches!(jr.0, r#enum::JsonResult::Ok(GoodT { x: 10 })));
+    }
+
+    #[test]
+    fn test_strict_rejects_ambiguity() {
+        let json = serde_json::json!({ "x": 10 });
+        let msg = StructRepr::<Amb, Amb, Untagged<Strict>>::try_from(json)
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("Ambiguous payload"));
+        assert!(msg.contains("Amb"));
+        assert!(msg.contains(r#"{"x":10}"#));
+    }
+
+    #[test]
+    fn test_strict_typed_error() {
+        let json = serde_json::json!({ "x": 10 });
+        let err = Untagged::<Strict>::from_value::<Amb, GoodT>(json).unwrap_err();
+
+        match err {
+            DecodeError::Ambiguous(a) => {
+                assert!(a.ok_type().ends_with("Amb"));
+                assert!(a.err_type().ends_with("GoodT"));
+                assert_eq!(a.payload(), &serde_json::json!({ "x": 10 }));
+            }
+            other => panic!("Expected Ambiguous, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_accepts_unambiguous() {
+        let json = serde_json::json!({ "msg": "boom" });
+        let jr = EnumRepr::<GoodT, BadE, Untagged<Strict>>::try_from(json).unwrap();
+        assert!(matches!(jr.0, r#enum::JsonResult::Err(_)));
+
+        let json = serde_json::json!({ "x": 1 });
+        let jr = EnumRepr::<GoodT, BadE, Untagged<Strict>>::try_from(json).unwrap();
+        assert!(matches!(jr.0, r#enum::JsonResult::Ok(_)));
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Created {
+        id: u32,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Rejected {
+        id: u32,
+        message: String,
+    }
+
+    #[test]
+    fn test_best_fit_takes_branch_that_ignored_less() {
+        let json = serde_json::json!({ "id": 7, "message": "quota exceeded" });
+
+        let jr: StructRepr<Created, Rejected, Untagged<PreferOk>> =
+            serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(jr.0.0, Ok(Created { id: 7 }));
+
+        let jr: StructRepr<Created, Rejected, Untagged<BestFit>> =
+            serde_json::from_value(json).unwrap();
+        assert_eq!(
+            jr.0.0,
+            Err(Rejected {
+                id: 7,
+                message: "quota exceeded".to_string()
+            })
+        );
+
+        let jr = EnumRepr::<Created, Rejected, Untagged<BestFit>>::try_from(
+            serde_json::json!({ "id": 7 }),
+        )
+        .unwrap();
+        assert!(matches!(jr.0, r#enum::JsonResult::Ok(Created { id: 7 })));
+    }
+
+    #[test]
+    fn test_best_fit_counts_nested_fields() {
+        #[derive(Debug, Deserialize)]
+        struct Wrapped<T> {
+            data: T,
+        }
+
+        let json = r#"{"data": {"id": 1, "message": "late", "retry": true}, "trace": "x"}"#;
+        let decoded =
+            Untagged::<BestFit>::from_str::<Wrapped<Created>, Wrapped<Rejected>>(json).unwrap();
+
+        assert_eq!(decoded.unwrap_err().data.message, "late");
+    }
+
+    #[test]
+    fn test_best_fit_tie_follows_policy() {
+        let json = r#"{"x": 3, "extra": null}"#;
+
+        let decoded = Untagged::<BestFit>::from_str::<Amb, GoodT>(json).unwrap();
+        assert_eq!(decoded, Ok(Amb { x: 3 }));
+
+        let decoded = Untagged::<BestFit<PreferErr>>::from_str::<Amb, GoodT>(json).unwrap();
+        assert_eq!(decoded, Err(GoodT { x: 3 }));
+
+        let err = Untagged::<BestFit<Strict>>::from_str::<Amb, GoodT>(json).unwrap_err();
+        let DecodeError::Ambiguous(a) = err else {
+            panic!("Expected Ambiguous, got {:?}", err);
+        };
+        assert_eq!(a.payload(), &serde_json::json!({ "x": 3, "extra": null }));
+    }
+
+    #[test]
+    fn test_best_fit_falls_back_when_one_branch_fails() {
+        let decoded =
+            Untagged::<BestFit<Strict>>::from_str::<GoodT, BadE>(r#"{"msg": "no", "at": 1}"#)
+                .unwrap();
+        assert_eq!(
+            decoded,
+            Err(BadE {
+                msg: "no".to_string()
+            })
+        );
+
+        let err = Untagged::<BestFit>::from_str::<GoodT, BadE>("[]").unwrap_err();
+        assert!(matches!(err, DecodeError::NoMatch(_)));
+    }
+
+    #[test]
+    fn test_no_match_message_is_unchanged_by_policy() {
+        let json = serde_json::json!([1, 2, 3]);
+
+        for msg in [
+            StructRepr::<GoodT, BadE, Untagged<PreferOk>>::try_from(json.clone())
+                .unwrap_err()
+                .to_string(),
+            StructRepr::<GoodT, BadE, Untagged<PreferErr>>::try_from(json.clone())
+                .unwrap_err()
+                .to_string(),
+            StructRepr::<GoodT, BadE, Untagged<Strict>>::try_from(json.clone())
+                .unwrap_err()
+                .to_string(),
+        ] {
+            let t_pos = msg.find("GoodT").unwrap();
+            let e_pos = msg.find("BadE").unwrap();
+            assert!(t_pos < e_pos);
+            assert!(msg.contains("Failed to parse"));
+        }
+    }
+
+    #[test]
+    fn test_typed_no_match_keeps_both_errors() {
+        let err = Untagged::<PreferOk>::from_str::<GoodT, BadE>(r#"{"y":1}"#).unwrap_err();
+
+        let DecodeError::NoMatch(err) = err else {
+            panic!("Expected NoMatch, got {:?}", err);
+        };
+        assert!(err.ok().type_name().ends_with("GoodT"));
+        assert!(err.err().type_name().ends_with("BadE"));
+        assert_eq!(err.ok().category(), Category::Data);
+        assert!(err.ok().error().to_string().contains("missing field `x`"));
+        assert!(
+            err.err()
+                .error()
+                .to_string()
+                .contains("missing field `msg`")
+        );
+
+        assert!(std::error::Error::source(&err).is_none());
+        let message = err.to_string();
+        assert!(message.contains("missing field `x`"));
+        assert!(message.contains("missing field `msg`"));
+    }
+
+    #[test]
+    fn test_branch_positions_from_text() {
+        let text = "{\n  \"x\": \"one\",\n  \"msg\": 7\n}";
+        let err = Untagged::<PreferOk>::from_str::<GoodT, BadE>(text).unwrap_err();
+
+        let DecodeError::NoMatch(err) = err else {
+            panic!("Expected NoMatch, got {:?}", err);
+        };
+        assert_eq!((err.err().line(), err.err().column()), (3, 10));
+        assert_eq!(err.err().path(), ".msg");
+        assert!(err.to_string().contains("at line 3 column 10"));
+
+        let value = serde_json::json!({ "x": "one", "msg": 7 });
+        let err = Untagged::<PreferOk>::from_value::<GoodT, BadE>(value).unwrap_err();
+        let DecodeError::NoMatch(err) = err else {
+            panic!("Expected NoMatch, got {:?}", err);
+        };
+        assert_eq!(err.err().line(), 0);
+    }
+
+    #[test]
+    fn test_branch_paths() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Item {
+            price: u32,
+        }
+
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Order {
+            items: Vec<Item>,
+        }
+
+        let json = serde_json::json!({
+            "items": [{ "price": 1 }, { "price": 2 }, { "price": 3 }, { "price": "free" }]
+        });
+        let err = Untagged::<PreferOk>::from_value::<Order, BadE>(json).unwrap_err();
+
+        let DecodeError::NoMatch(err) = err else {
+            panic!("Expected NoMatch, got {:?}", err);
+        };
+        assert_eq!(err.ok().path(), ".items[3].price");
+        assert_eq!(err.err().path(), ".");
+
+        let msg = err.to_string();
+        assert!(msg.contains("(at .items[3].price)"));
+    }
+
+    #[test]
+    fn test_closest_match_diagnostics() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct User {
+            id: u32,
+            name: String,
+            email: String,
+        }
+
+        let json = serde_json::json!({ "id": 1, "emial": "a@b.c" });
+        let err = Untagged::<PreferOk>::from_value::<User, BadE>(json).unwrap_err();
+
+        let DecodeError::NoMatch(err) = err else {
+            panic!("Expected NoMatch, got {:?}", err);
+        };
+        assert_eq!(err.closest(), Branch::Ok);
+        assert_eq!(err.ok().diagnosis().missing(), &["name", "email"]);
+        assert_eq!(err.err().diagnosis().missing(), &["msg"]);
+
+        #[cfg(feature = "std")]
+        assert!(err.to_string().ends_with(
+            "Closest match: json_result::repr::untagged::tests::test_closest_match_diagnostics::User: \
+             missing `name`, `email`; unexpected `emial` (did you mean `email`?)"
+        ));
+    }
+
+    #[test]
+    fn test_closest_match_tie() {
+        let err = Untagged::<PreferOk>::from_str::<GoodT, BadE>("[]").unwrap_err();
+
+        let DecodeError::NoMatch(err) = err else {
+            panic!("Expected NoMatch, got {:?}", err);
+        };
+        assert_eq!(err.closest(), Branch::Either);
+        assert!(!err.to_string().contains("Closest match"));
+    }
+
+    #[test]
+    fn test_typed_syntax_error_is_not_a_branch_failure() {
+        let err = Untagged::<Strict>::from_slice::<GoodT, BadE>(b"{ not json").unwrap_err();
+
+        match err {
+            DecodeError::Json(e) => assert_eq!(e.classify(), Category::Syntax),
+            other => panic!("Expected Json, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_no_match_converts_to_serde_json_error() {
+        let err = Untagged::<PreferOk>::from_str::<GoodT, BadE>("[]").unwrap_err();
+        let expected = err.to_string();
+
+        let err: serde_json::Error = err.into();
+        assert_eq!(err.to_string(), expected);
+        assert!(expected.starts_with("Failed to parse as"));
+    }
+
+    #[test]
+    fn test_repr_borrows_from_input() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Greeting<'a> {
+            text: &'a str,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Refusal<'a> {
+            reason: &'a str,
+        }
+
+        let input = r#"{"reason": "busy"}"#;
+        let jr: StructRepr<Greeting<'_>, Refusal<'_>, Untagged<BestFit>> =
+            serde_json::from_str(input).unwrap();
+        assert_eq!(jr.0.0, Err(Refusal { reason: "busy" }));
+
+        let input = r#"{"text": "hello"}"#;
+        let jr: StructRepr<Greeting<'_>, Refusal<'_>, Untagged<Strict>> =
+            serde_json::from_str(input).unwrap();
+        assert_eq!(jr.0.0, Ok(Greeting { text: "hello" }));
+    }
+
+    #[test]
+    fn test_serialization_is_untagged() {
+        let jr = StructRepr::<i32, &str, Untagged<Strict>>::new(r#struct::JsonResult(Err("boom")));
+        assert_eq!(serde_json::to_string(&jr).unwrap(), "\"boom\"");
+    }
+}
@@ -0,0 +1,516 @@
+//! Declarative discrimination rules.
+//!
+//! A [`RuleBook`] maps upstream names to [`RuleSet`]s loaded from JSON (or TOML with the `toml`
+//! feature). Each rule pairs a JSON Pointer predicate with the branch it selects:
+//!
+//! ```json
+//! {
+//!   "billing": {
+//!     "rules": [
+//!       { "when": "/status == \"ok\"", "then": "ok" },
+//!       { "when": "exists(/error)", "then": "err" },
+//!       { "when": "/code in [0, 200]", "then": "ok" }
+//!     ],
+//!     "otherwise": "either"
+//!   }
+//! }
+//! ```
+//!
+//! Supported predicates are `exists(<pointer>)`, `<pointer> == <json>`, `<pointer> != <json>`
+//! and `<pointer> in [<json>, ...]`. A pointer ends at the first whitespace, `=` or `!`.
+//!
+//! Rules are tried in order and the first match wins. `otherwise` picks the branch when no rule
+//! matches; when it is left out, such payloads are rejected with an error that lists why each
+//! rule did not match.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::classify::{Branch, Classifier};
+
+/// Rule sets keyed by upstream name.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct RuleBook {
+    upstreams: HashMap<String, RuleSet>,
+}
+
+impl RuleBook {
+    /// Parses a rule book from JSON.
+    pub fn from_json_str(s: &str) -> Result<Self, RulesError> {
+        serde_json::from_str(s).map_err(RulesError::Json)
+    }
+
+    /// Parses a rule book from TOML.
+    #[cfg(feature = "toml")]
+    pub fn from_toml_str(s: &str) -> Result<Self, RulesError> {
+        toml::from_str(s).map_err(RulesError::Toml)
+    }
+
+    /// Loads a rule book from a file. Files ending in `.toml` are read as TOML, anything else
+    /// as JSON.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, RulesError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path).map_err(RulesError::Io)?;
+
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml_str(&contents),
+            #[cfg(not(feature = "toml"))]
+            Some("toml") => Err(RulesError::Unsupported("toml")),
+            _ => Self::from_json_str(&contents),
+        }
+    }
+
+    /// Returns the rule set for `upstream`.
+    pub fn get(&self, upstream: &str) -> Option<&RuleSet> {
+        self.upstreams.get(upstream)
+    }
+}
+
+/// An ordered list of rules for one upstream. Implements [`Classifier`].
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    #[serde(default)]
+    otherwise: Option<Branch>,
+}
+
+impl RuleSet {
+    /// Creates a rule set. `otherwise` is used when no rule matches; `None` rejects the payload.
+    pub fn new(rules: Vec<Rule>, otherwise: Option<Branch>) -> Self {
+        RuleSet { rules, otherwise }
+    }
+
+    /// The rules, in evaluation order.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    fn first_match(&self, value: &Value) -> Option<(usize, &Rule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.when.evaluate(value).is_ok())
+    }
+}
+
+impl Classifier for RuleSet {
+    type Error = NoRuleMatched;
+
+    fn classify(&self, value: &Value) -> Result<Branch, Self::Error> {
+        if let Some((_, rule)) = self.first_match(value) {
+            return Ok(rule.then);
+        }
+
+        self.otherwise.ok_or_else(|| NoRuleMatched {
+            misses: self
+                .rules
+                .iter()
+                .map(|rule| {
+                    let why = rule.when.evaluate(value).err().unwrap_or_default();
+                    (rule.when.to_string(), why)
+                })
+                .collect(),
+        })
+    }
+
+    fn explain(&self, value: &Value) -> Option<String> {
+        match self.first_match(value) {
+            Some((i, rule)) => Some(format!("rule {} `{}` matched", i + 1, rule.when)),
+            None => Some("no rule matched, used `otherwise`".to_string()),
+        }
+    }
+}
+
+/// A predicate and the branch it selects.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    when: Predicate,
+    then: Branch,
+}
+
+impl Rule {
+    /// Creates a rule selecting `then` when `when` holds.
+    pub fn new(when: Predicate, then: Branch) -> Self {
+        Rule { when, then }
+    }
+}
+
+/// A JSON Pointer predicate, parsed from strings such as `/status == "ok"`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Predicate {
+    source: String,
+    test: Test,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Test {
+    Exists(String),
+    Eq(String, Value),
+    Ne(String, Value),
+    In(String, Vec<Value>),
+}
+
+impl Predicate {
+    /// Returns `Ok(())` if the predicate holds for `value`, or why it does not.
+    fn evaluate(&self, value: &Value) -> Result<(), String> {
+        let (pointer, found) = match &self.test {
+            Test::Exists(p) | Test::Eq(p, _) | Test::Ne(p, _) | Test::In(p, _) => {
+                (p, value.pointer(p))
+            }
+        };
+        let describe = || match found {
+            Some(v) => format!("{} is {}", pointer, v),
+            None => format!("{} is missing", pointer),
+        };
+
+        let holds = match (&self.test, found) {
+            (Test::Exists(_), found) => found.is_some(),
+            (Test::Eq(_, expected), Some(v)) => v == expected,
+            (Test::Ne(_, expected), found) => found != Some(expected),
+            (Test::In(_, options), Some(v)) => options.contains(v),
+            (_, None) => false,
+        };
+
+        if holds { Ok(()) } else { Err(describe()) }
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let src = s.trim();
+        let invalid = |why: &str| format!("invalid predicate `{}`: {}", src, why);
+
+        let test = if let Some(inner) = src
+            .strip_prefix("exists(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            Test::Exists(pointer(inner.trim()).map_err(|e| invalid(&e))?)
+        } else {
+            let end = src
+                .find(|c: char| c.is_whitespace() || c == '=' || c == '!')
+                .unwrap_or(src.len());
+            let ptr = pointer(&src[..end]).map_err(|e| invalid(&e))?;
+            let rest = src[end..].trim_start();
+
+            let literal = |text: &str| {
+                serde_json::from_str::<Value>(text.trim())
+                    .map_err(|e| invalid(&format!("bad JSON literal: {}", e)))
+            };
+
+            if let Some(text) = rest.strip_prefix("==") {
+                Test::Eq(ptr, literal(text)?)
+            } else if let Some(text) = rest.strip_prefix("!=") {
+                Test::Ne(ptr, literal(text)?)
+            } else if let Some(text) = rest.strip_prefix("in") {
+                match literal(text)? {
+                    Value::Array(options) => Test::In(ptr, options),
+                    _ => return Err(invalid("`in` expects a JSON array")),
+                }
+            } else {
+                return Err(invalid("expected `==`, `!=`, `in` or `exists(...)`"));
+            }
+        };
+
+        Ok(Predicate {
+            source: src.to_string(),
+            test,
+        })
+    }
+}
+
+impl TryFrom<String> for Predicate {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn pointer(p: &str) -> Result<String, String> {
+    if p.is_empty() || p.starts_with('/') {
+        Ok(p.to_string())
+    } else {
+        Err(format!("`{}` is not a JSON Pointer", p))
+    }
+}
+
+/// No rule matched a payload and the rule set has no `otherwise` branch.
+#[derive(Debug, Clone)]
+pub struct NoRuleMatched {
+    misses: Vec<(String, String)>,
+}
+
+impl NoRuleMatched {
+    /// Each rule's predicate with the reason it did not match.
+    pub fn misses(&self) -> &[(String, String)] {
+        &self.misses
+    }
+}
+
+impl fmt::Display for NoRuleMatched {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no rule matched")?;
+        for (i, (rule, why)) in self.misses.iter().enumerate() {
+            let sep = if i == 0 { ": " } else { "; " };
+            write!(f, "{}`{}` ({})", sep, rule, why)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NoRuleMatched {}
+
+/// Error loading a [`RuleBook`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum RulesError {
+    /// The file could not be read.
+    Io(std::io::Error),
+    /// The JSON was malformed or a predicate failed to parse.
+    Json(serde_json::Error),
+    /// The TOML was malformed or a predicate failed to parse.
+    #[cfg(feature = "toml")]
+    Toml(toml::de::Error),
+    /// The file format needs a cargo feature that is not enabled.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Io(e) => write!(f, "failed to read rules: {}", e),
+            RulesError::Json(e) => write!(f, "invalid JSON rules: {}", e),
+            #[cfg(feature = "toml")]
+            RulesError::Toml(e) => write!(f, "invalid TOML rules: {}", e),
+            RulesError::Unsupported(format) => {
+                write!(f, "{} rules need the `{}` feature", format, format)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RulesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RulesError::Io(e) => Some(e),
+            RulesError::Json(e) => Some(e),
+            #[cfg(feature = "toml")]
+            RulesError::Toml(e) => Some(e),
+            RulesError::Unsupported(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeSeed;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::{Predicate, Rule, RuleBook, RuleSet};
+    use crate::classify::{self, Branch, Classifier, ClassifySeed};
+    use crate::error::DecodeError;
+    use crate::{r#enum, r#struct};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Data {
+        #[serde(default)]
+        items: Vec<u32>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Failure {
+        error: String,
+    }
+
+    const BOOK: &str = r#"{
+        "billing": {
+            "rules": [
+                { "when": "/status == \"ok\"", "then": "ok" },
+                { "when": "exists(/error)", "then": "err" },
+                { "when": "/code in [0, 200]", "then": "ok" }
+            ]
+        },
+        "search": {
+            "rules": [{ "when": "/meta/code != 0", "then": "err" }],
+            "otherwise": "either"
+        }
+    }"#;
+
+    fn billing() -> RuleSet {
+        RuleBook::from_json_str(BOOK)
+            .unwrap()
+            .get("billing")
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn test_predicates() {
+        let p: Predicate = "/status == \"ok\"".parse().unwrap();
+        assert!(p.evaluate(&json!({ "status": "ok" })).is_ok());
+        assert_eq!(
+            p.evaluate(&json!({ "status": "down" })).unwrap_err(),
+            "/status is \"down\""
+        );
+
+        let p: Predicate = "exists(/a/b)".parse().unwrap();
+        assert!(p.evaluate(&json!({ "a": { "b": null } })).is_ok());
+        assert_eq!(
+            p.evaluate(&json!({ "a": {} })).unwrap_err(),
+            "/a/b is missing"
+        );
+
+        let p: Predicate = "/code in [0, 200]".parse().unwrap();
+        assert!(p.evaluate(&json!({ "code": 200 })).is_ok());
+        assert!(p.evaluate(&json!({ "code": 500 })).is_err());
+
+        let p: Predicate = "/code!=0".parse().unwrap();
+        assert!(p.evaluate(&json!({ "code": 1 })).is_ok());
+        assert!(p.evaluate(&json!({})).is_ok());
+        assert!(p.evaluate(&json!({ "code": 0 })).is_err());
+    }
+
+    #[test]
+    fn test_invalid_predicates() {
+        assert!("status == 1".parse::<Predicate>().is_err());
+        assert!("/status ~= 1".parse::<Predicate>().is_err());
+        assert!("/status == ok".parse::<Predicate>().is_err());
+        assert!("/code in 1".parse::<Predicate>().is_err());
+
+        let err =
+            RuleBook::from_json_str(r#"{ "x": { "rules": [{ "when": "nope", "then": "ok" }] } }"#)
+                .unwrap_err()
+                .to_string();
+        assert!(err.contains("invalid predicate `nope`"));
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let rules = billing();
+
+        assert_eq!(
+            rules
+                .classify(&json!({ "status": "ok", "error": "x" }))
+                .unwrap(),
+            Branch::Ok
+        );
+        assert_eq!(
+            rules.classify(&json!({ "error": "x" })).unwrap(),
+            Branch::Err
+        );
+        assert_eq!(rules.classify(&json!({ "code": 0 })).unwrap(), Branch::Ok);
+    }
+
+    #[test]
+    fn test_no_rule_matched_explains_each_rule() {
+        let json = json!({ "status": "pending", "code": 500 });
+        let err = classify::from_value::<r#struct::JsonResult<Data, Failure>, _>(json, &billing())
+            .unwrap_err();
+
+        assert!(matches!(err, DecodeError::Unclassified(_)));
+        let msg = err.to_string();
+        assert!(msg.contains("no rule matched"));
+        assert!(msg.contains("`/status == \"ok\"` (/status is \"pending\")"));
+        assert!(msg.contains("`exists(/error)` (/error is missing)"));
+        assert!(msg.contains("`/code in [0, 200]` (/code is 500)"));
+    }
+
+    #[test]
+    fn test_mismatch_names_matching_rule() {
+        let json = json!({ "error": 42 });
+        let msg = classify::from_value::<r#enum::JsonResult<Data, Failure>, _>(json, &billing())
+            .unwrap_err()
+            .to_string();
+
+        assert!(msg.contains("Classified as Err (rule 2 `exists(/error)` matched)"));
+        assert!(msg.contains("Failure"));
+    }
+
+    #[test]
+    fn test_otherwise_branch() {
+        let book = RuleBook::from_json_str(BOOK).unwrap();
+        let search = book.get("search").unwrap();
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"meta":{"code":0},"items":[1]}"#);
+        let jr = ClassifySeed::<r#struct::JsonResult<Data, Failure>, _>::new(search)
+            .deserialize(&mut de)
+            .unwrap();
+        assert_eq!(jr.0, Ok(Data { items: vec![1] }));
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"meta":{"code":3},"error":"x"}"#);
+        let jr = ClassifySeed::<r#struct::JsonResult<Data, Failure>, _>::new(search)
+            .deserialize(&mut de)
+            .unwrap();
+        assert_eq!(jr.0, Err(Failure { error: "x".into() }));
+    }
+
+    #[test]
+    fn test_built_in_code() {
+        let rules = RuleSet::new(
+            vec![Rule::new("/ok == true".parse().unwrap(), Branch::Ok)],
+            Some(Branch::Err),
+        );
+
+        let jr: r#struct::JsonResult<serde_json::Value, Failure> =
+            classify::from_value(json!({ "ok": false, "error": "x" }), &rules).unwrap();
+        assert!(jr.is_err());
+    }
+
+    #[test]
+    fn test_from_path() {
+        let path =
+            std::env::temp_dir().join(format!("json-result-rules-{}.json", std::process::id()));
+        std::fs::write(&path, BOOK).unwrap();
+
+        let book = RuleBook::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(book.get("billing").unwrap().rules().len(), 3);
+        assert!(book.get("unknown").is_none());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_rules() {
+        let book = RuleBook::from_toml_str(
+            r#"
+            [billing]
+            otherwise = "err"
+
+            [[billing.rules]]
+            when = '/status == "ok"'
+            then = "ok"
+            "#,
+        )
+        .unwrap();
+        let rules = book.get("billing").unwrap();
+
+        assert_eq!(
+            rules.classify(&json!({ "status": "ok" })).unwrap(),
+            Branch::Ok
+        );
+        assert_eq!(
+            rules.classify(&json!({ "status": "no" })).unwrap(),
+            Branch::Err
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! simd-json backend.
+//!
+//! Decodes with [simd-json](https://docs.rs/simd-json) instead of `serde_json`, using the same
+//! try-`T`-then-`E` rules. The input is recorded once, exactly as on the `serde_json` path, so
+//! both backends pick the same branch and report the same errors.
+//!
+//! ```rust
+//! use json_result::r#struct::JsonResult;
+//!
+//! let mut input = br#"{"code": 404}"#.to_vec();
+//! let jr: JsonResult<Vec<u32>, serde_json::Value> =
+//!     json_result::simd::from_slice(&mut input).unwrap();
+//!
+//! assert!(jr.0.is_err());
+//! ```
+
+use serde::{Deserialize, Serialize};
+use simd_json::{BorrowedValue, OwnedValue};
+
+use crate::error::DecodeError;
+use crate::repr::ResultLike;
+use crate::repr::untagged::{self, Ambiguity};
+use crate::tape::Tape;
+use crate::{r#enum, r#struct};
+
+/// Parses `input` with simd-json and decodes it as `T`, falling back to `E`.
+///
+/// simd-json parses in place, so the buffer is modified. Both branches may borrow from it.
+pub fn from_slice<'de, J>(input: &'de mut [u8]) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    let tape: Tape<'de> = simd_json::serde::from_slice(input).map_err(DecodeError::SimdJson)?;
+    decode(tape)
+}
+
+/// Decodes an already parsed [`OwnedValue`] as `T`, falling back to `E`.
+pub fn from_owned_value<'de, J>(value: &'de OwnedValue) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    decode(Tape::deserialize(value).map_err(DecodeError::SimdJson)?)
+}
+
+/// Decodes an already parsed [`BorrowedValue`] as `T`, falling back to `E`.
+pub fn from_borrowed_value<'de, J>(value: &'de BorrowedValue<'de>) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    decode(Tape::deserialize(value).map_err(DecodeError::SimdJson)?)
+}
+
+fn decode<'de, J>(tape: Tape<'de>) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    untagged::decode(tape, Ambiguity::PreferOk).map(J::from_result)
+}
+
+fn to_owned_value<V: Serialize>(v: &V) -> OwnedValue {
+    simd_json::serde::to_owned_value(v).expect("value serializes to JSON")
+}
+
+impl<T, E> From<r#struct::JsonResult<T, E>> for OwnedValue
+where
+    T: Serialize,
+    E: Serialize,
+{
+    fn from(value: r#struct::JsonResult<T, E>) -> Self {
+        match value.0 {
+            Ok(v) => to_owned_value(&v),
+            Err(e) => to_owned_value(&e),
+        }
+    }
+}
+
+impl<T, E> From<r#enum::JsonResult<T, E>> for OwnedValue
+where
+    T: Serialize,
+    E: Serialize,
+{
+    fn from(value: r#enum::JsonResult<T, E>) -> Self {
+        match value {
+            r#enum::JsonResult::Ok(v) => to_owned_value(&v),
+            r#enum::JsonResult::Err(e) => to_owned_value(&e),
+        }
+    }
+}
+
+impl<T, E> TryFrom<OwnedValue> for r#struct::JsonResult<T, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::DeserializeOwned,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        from_owned_value(&value)
+    }
+}
+
+impl<'de, T, E> TryFrom<&'de OwnedValue> for r#struct::JsonResult<T, E>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: &'de OwnedValue) -> Result<Self, Self::Error> {
+        from_owned_value(value)
+    }
+}
+
+impl<T, E> TryFrom<OwnedValue> for r#enum::JsonResult<T, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::DeserializeOwned,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        from_owned_value(&value)
+    }
+}
+
+impl<'de, T, E> TryFrom<&'de OwnedValue> for r#enum::JsonResult<T, E>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: &'de OwnedValue) -> Result<Self, Self::Error> {
+        from_owned_value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde::de::DeserializeOwned;
+    use simd_json::OwnedValue;
+
+    use crate::conformance::{self, ApiError, Backend, User};
+    use crate::error::DecodeError;
+    use crate::repr::ResultLike;
+    use crate::{r#enum, r#struct};
+
+    struct Simd;
+
+    impl Backend for Simd {
+        fn from_text<T, E>(input: &str) -> Result<Result<T, E>, DecodeError>
+        where
+            T: DeserializeOwned,
+            E: DeserializeOwned,
+        {
+            let mut bytes = input.as_bytes().to_vec();
+            super::from_slice::<r#struct::JsonResult<T, E>>(&mut bytes).map(|jr| jr.0)
+        }
+
+        fn from_value<T, E>(input: &str) -> Result<Result<T, E>, DecodeError>
+        where
+            T: DeserializeOwned,
+            E: DeserializeOwned,
+        {
+            let parsed: OwnedValue = simd_json::to_owned_value(&mut input.as_bytes().to_vec())
+                .expect("conformance inputs are valid JSON");
+            r#enum::JsonResult::<T, E>::try_from(&parsed).map(ResultLike::into_result)
+        }
+    }
+
+    #[test]
+    fn test_conformance_with_serde_json() {
+        conformance::check::<Simd>();
+    }
+
+    #[test]
+    fn test_invalid_json_is_a_parse_error() {
+        let mut bytes = b"{ not json".to_vec();
+        let err = super::from_slice::<r#struct::JsonResult<User, ApiError>>(&mut bytes);
+
+        assert!(matches!(err, Err(DecodeError::SimdJson(_))));
+    }
+
+    #[test]
+    fn test_borrows_from_buffer() {
+        let mut bytes = br#"{"name":"zero-copy"}"#.to_vec();
+
+        #[derive(Debug, Deserialize)]
+        struct Named<'a> {
+            name: &'a str,
+        }
+
+        let jr = super::from_slice::<r#struct::JsonResult<Named<'_>, u32>>(&mut bytes).unwrap();
+        assert_eq!(jr.0.unwrap().name, "zero-copy");
+    }
+
+    #[test]
+    fn test_into_owned_value() {
+        let ok = r#struct::JsonResult::<User, ApiError>(Ok(User {
+            id: 7,
+            name: "ann".to_string(),
+            email: None,
+        }));
+        let value: OwnedValue = ok.into();
+        let back = r#struct::JsonResult::<User, ApiError>::try_from(&value).unwrap();
+        assert_eq!(back.0.unwrap().name, "ann");
+        let back = r#enum::JsonResult::<User, ApiError>::try_from(value).unwrap();
+        assert!(matches!(back, r#enum::JsonResult::Ok(User { id: 7, .. })));
+
+        let err = r#enum::JsonResult::<User, ApiError>::Err(ApiError {
+            code: 1,
+            message: "x".to_string(),
+        });
+        let value: OwnedValue = err.into();
+        let back = r#struct::JsonResult::<User, ApiError>::try_from(value.clone()).unwrap();
+        assert_eq!(back.0.unwrap_err().code, 1);
+        let back = r#enum::JsonResult::<User, ApiError>::try_from(value).unwrap();
+        assert!(matches!(
+            back,
+            r#enum::JsonResult::Err(ApiError { code: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,231 @@
+//! sonic-rs backend.
+//!
+//! Decodes with [sonic-rs](https://docs.rs/sonic-rs) instead of `serde_json`, using the same
+//! try-`T`-then-`E` rules. The input is recorded once, exactly as on the `serde_json` path, so
+//! both backends pick the same branch and report the same errors.
+//!
+//! ```rust
+//! use json_result::r#struct::JsonResult;
+//!
+//! let jr: JsonResult<Vec<u32>, serde_json::Value> =
+//!     json_result::sonic::from_str(r#"{"code": 404}"#).unwrap();
+//!
+//! assert!(jr.0.is_err());
+//! ```
+
+use serde::{Deserialize, Serialize};
+use sonic_rs::Value;
+
+use crate::error::DecodeError;
+use crate::repr::ResultLike;
+use crate::repr::untagged::{self, Ambiguity};
+use crate::tape::Tape;
+use crate::{r#enum, r#struct};
+
+/// Parses `input` with sonic-rs and decodes it as `T`, falling back to `E`.
+///
+/// Both branches may borrow from `input`.
+pub fn from_slice<'de, J>(input: &'de [u8]) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    let tape: Tape<'de> = sonic_rs::from_slice(input).map_err(DecodeError::SonicRs)?;
+    decode(tape)
+}
+
+/// Like [`from_slice`], for a string.
+pub fn from_str<'de, J>(input: &'de str) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    let tape: Tape<'de> = sonic_rs::from_str(input).map_err(DecodeError::SonicRs)?;
+    decode(tape)
+}
+
+/// Decodes an already parsed [`Value`] as `T`, falling back to `E`.
+pub fn from_value<'de, J>(value: &'de Value) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    decode(Tape::deserialize(value).map_err(DecodeError::SonicRs)?)
+}
+
+fn decode<'de, J>(tape: Tape<'de>) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de>,
+    J::Err: Deserialize<'de>,
+{
+    untagged::decode(tape, Ambiguity::PreferOk).map(J::from_result)
+}
+
+fn to_value<V: Serialize>(v: &V) -> Value {
+    sonic_rs::to_value(v).expect("value serializes to JSON")
+}
+
+impl<T, E> From<r#struct::JsonResult<T, E>> for Value
+where
+    T: Serialize,
+    E: Serialize,
+{
+    fn from(value: r#struct::JsonResult<T, E>) -> Self {
+        match value.0 {
+            Ok(v) => to_value(&v),
+            Err(e) => to_value(&e),
+        }
+    }
+}
+
+impl<T, E> From<r#enum::JsonResult<T, E>> for Value
+where
+    T: Serialize,
+    E: Serialize,
+{
+    fn from(value: r#enum::JsonResult<T, E>) -> Self {
+        match value {
+            r#enum::JsonResult::Ok(v) => to_value(&v),
+            r#enum::JsonResult::Err(e) => to_value(&e),
+        }
+    }
+}
+
+impl<T, E> TryFrom<Value> for r#struct::JsonResult<T, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::DeserializeOwned,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_value(&value)
+    }
+}
+
+impl<'de, T, E> TryFrom<&'de Value> for r#struct::JsonResult<T, E>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: &'de Value) -> Result<Self, Self::Error> {
+        from_value(value)
+    }
+}
+
+impl<T, E> TryFrom<Value> for r#enum::JsonResult<T, E>
+where
+    T: serde::de::DeserializeOwned,
+    E: serde::de::DeserializeOwned,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_value(&value)
+    }
+}
+
+impl<'de, T, E> TryFrom<&'de Value> for r#enum::JsonResult<T, E>
+where
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
+{
+    type Error = DecodeError;
+
+    fn try_from(value: &'de Value) -> Result<Self, Self::Error> {
+        from_value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde::de::DeserializeOwned;
+    use sonic_rs::Value;
+
+    use crate::conformance::{self, ApiError, Backend, User};
+    use crate::error::DecodeError;
+    use crate::repr::ResultLike;
+    use crate::{r#enum, r#struct};
+
+    struct Sonic;
+
+    impl Backend for Sonic {
+        fn from_text<T, E>(input: &str) -> Result<Result<T, E>, DecodeError>
+        where
+            T: DeserializeOwned,
+            E: DeserializeOwned,
+        {
+            super::from_str::<r#struct::JsonResult<T, E>>(input).map(|jr| jr.0)
+        }
+
+        fn from_value<T, E>(input: &str) -> Result<Result<T, E>, DecodeError>
+        where
+            T: DeserializeOwned,
+            E: DeserializeOwned,
+        {
+            let parsed: Value =
+                sonic_rs::from_str(input).expect("conformance inputs are valid JSON");
+            r#enum::JsonResult::<T, E>::try_from(&parsed).map(ResultLike::into_result)
+        }
+    }
+
+    #[test]
+    fn test_conformance_with_serde_json() {
+        conformance::check::<Sonic>();
+    }
+
+    #[test]
+    fn test_invalid_json_is_a_parse_error() {
+        let err = super::from_slice::<r#struct::JsonResult<User, ApiError>>(b"{ not json");
+        assert!(matches!(err, Err(DecodeError::SonicRs(_))));
+
+        let err = super::from_str::<r#struct::JsonResult<User, ApiError>>("1 2");
+        assert!(matches!(err, Err(DecodeError::SonicRs(_))));
+    }
+
+    #[test]
+    fn test_borrows_from_input() {
+        #[derive(Debug, Deserialize)]
+        struct Named<'a> {
+            name: &'a str,
+        }
+
+        let input = br#"{"name":"zero-copy"}"#;
+        let jr = super::from_slice::<r#struct::JsonResult<Named<'_>, u32>>(input).unwrap();
+        assert_eq!(jr.0.unwrap().name, "zero-copy");
+    }
+
+    #[test]
+    fn test_value_round_trip() {
+        let ok = r#struct::JsonResult::<User, ApiError>(Ok(User {
+            id: 7,
+            name: "ann".to_string(),
+            email: None,
+        }));
+        let value: Value = ok.into();
+        let back = r#struct::JsonResult::<User, ApiError>::try_from(&value).unwrap();
+        assert_eq!(back.0.unwrap().name, "ann");
+        let back = r#enum::JsonResult::<User, ApiError>::try_from(value).unwrap();
+        assert!(matches!(back, r#enum::JsonResult::Ok(User { id: 7, .. })));
+
+        let err = r#enum::JsonResult::<User, ApiError>::Err(ApiError {
+            code: 1,
+            message: "x".to_string(),
+        });
+        let value: Value = err.into();
+        let back = r#struct::JsonResult::<User, ApiError>::try_from(value.clone()).unwrap();
+        assert_eq!(back.0.unwrap_err().code, 1);
+        let back = r#enum::JsonResult::<User, ApiError>::try_from(value).unwrap();
+        assert!(matches!(
+            back,
+            r#enum::JsonResult::Err(ApiError { code: 1, .. })
+        ));
+    }
+}
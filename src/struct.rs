@@ -1,7 +1,22 @@
-use std::ops::{Deref, DerefMut};
-
-use serde::de::{DeserializeOwned, Error as DeError};
-use serde::{Deserialize, Serialize};
+use core::ops::{Deref, DerefMut};
+
+use serde::Serialize;
+#[cfg(feature = "alloc")]
+use serde::{Deserialize, de::Error as DeError};
+
+#[cfg(feature = "alloc")]
+use serde_json::Value;
+
+#[cfg(feature = "alloc")]
+use crate::drift::Reported;
+#[cfg(feature = "alloc")]
+use crate::error::DecodeError;
+#[cfg(feature = "alloc")]
+use crate::repr::ResultLike;
+#[cfg(feature = "alloc")]
+use crate::repr::untagged::{self, Ambiguity};
+#[cfg(feature = "alloc")]
+use crate::tape::Tape;
 
 /// JsonResult<T, E>
 ///
@@ -11,6 +26,9 @@ use serde::{Deserialize, Serialize};
 /// This is useful when a response payload may be either a success object or an error object
 /// and you want to round-trip that semantics with serde.
 ///
+/// The type and its `Serialize` impl are available in `no_std` builds without `alloc`, for
+/// example with serde-json-core; deserializing needs the `alloc` feature.
+///
 /// # Examples
 ///
 /// Serialize an Ok value:
@@ -49,39 +67,49 @@ where
     }
 }
 
+#[cfg(feature = "alloc")]
 impl<'de, T, E> Deserialize<'de> for JsonResult<T, E>
 where
-    T: DeserializeOwned,
-    E: DeserializeOwned,
+    T: Deserialize<'de>,
+    E: Deserialize<'de>,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        // First try to deserialize as T (Ok)
-        let value = serde_json::Value::deserialize(deserializer)?;
-
-        let try_t: Result<T, _> = serde_json::from_value(value.clone());
-        let try_e: Result<E, _> = serde_json::from_value(value.clone());
-
-        match (try_t, try_e) {
-            (Ok(v), _) => Ok(JsonResult(Ok(v))),
-            (_, Ok(e)) => Ok(JsonResult(Err(e))),
-            (Err(t_err), Err(e_err)) => {
-                let t_name = std::any::type_name::<T>();
-                let e_name = std::any::type_name::<E>();
-
-                let msg = format!(
-                    "Failed to parse as {}: {}\nFailed to parse as {}: {}",
-                    t_name, t_err, e_name, e_err
-                );
+        // Try T (Ok) first, then E (Err)
+        let tape = Tape::deserialize(deserializer)?;
 
-                Err(DeError::custom(msg))
-            }
-        }
+        untagged::decode(tape, Ambiguity::PreferOk)
+            .map(JsonResult)
+            .map_err(DeError::custom)
     }
 }
 
+#[cfg(feature = "alloc")]
+impl<T, E> JsonResult<T, E> {
+    /// Parses `s` like deserializing a `JsonResult` does, and also returns the paths of the
+    /// keys the chosen branch ignored; see [`drift`](crate::drift).
+    pub fn from_str_reported<'de>(s: &'de str) -> Result<Reported<Self>, DecodeError>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+    {
+        Reported::from_str(s)
+    }
+
+    /// Like [`JsonResult::from_str_reported`], for a `serde_json::Value` both branches may
+    /// borrow from.
+    pub fn from_value_reported<'de>(value: &'de Value) -> Result<Reported<Self>, DecodeError>
+    where
+        T: Deserialize<'de>,
+        E: Deserialize<'de>,
+    {
+        Reported::from_value(value)
+    }
+}
+
+#[cfg(feature = "alloc")]
 impl<T, E> From<JsonResult<T, E>> for serde_json::Value
 where
     T: Serialize,
@@ -116,6 +144,24 @@ impl<T, E> From<Result<T, E>> for JsonResult<T, E> {
     }
 }
 
+#[cfg(feature = "alloc")]
+impl<T, E> ResultLike for JsonResult<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn from_result(result: Result<T, E>) -> Self {
+        JsonResult(result)
+    }
+
+    fn as_result(&self) -> Result<&T, &E> {
+        self.0.as_ref()
+    }
+
+    fn into_result(self) -> Result<T, E> {
+        self.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::DerefMut;
@@ -379,4 +425,188 @@ mod tests {
         let jr: JsonResult<i32, &str> = r.into();
         assert_eq!(jr.0.unwrap_err(), "fail");
     }
+
+    #[test]
+    fn test_borrows_from_input() {
+        use std::borrow::Cow;
+
+        #[derive(Debug, Deserialize)]
+        struct Record<'a> {
+            level: &'a str,
+            #[serde(borrow)]
+            message: Cow<'a, str>,
+        }
+
+        let input = r#"{"level":"info","message":"started"}"#;
+        let jr: JsonResult<Record<'_>, Cow<'_, str>> = serde_json::from_str(input).unwrap();
+        let record = jr.0.unwrap();
+
+        assert_eq!(record.level, "info");
+        assert!(matches!(record.message, Cow::Borrowed("started")));
+        assert!(
+            input
+                .as_bytes()
+                .as_ptr_range()
+                .contains(&record.level.as_ptr())
+        );
+
+        let jr: JsonResult<u32, &str> = serde_json::from_str(r#""fail""#).unwrap();
+        assert_eq!(jr.0, Err("fail"));
+    }
+
+    #[test]
+    fn test_borrowed_failure_keeps_diagnostics() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Record<'a> {
+            level: &'a str,
+        }
+
+        let err = serde_json::from_str::<JsonResult<Record<'_>, u32>>(r#"{"levl":"x"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("missing field `level`"));
+        #[cfg(feature = "std")]
+        assert!(err.contains("(did you mean `level`?)"));
+    }
+
+    #[cfg(any(
+        feature = "cbor",
+        feature = "msgpack",
+        feature = "yaml",
+        feature = "toml"
+    ))]
+    mod formats {
+        use std::collections::BTreeMap;
+
+        use serde::{Deserialize, Serialize};
+
+        use super::JsonResult;
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Telemetry {
+            device: String,
+            sequence: u64,
+            readings: BTreeMap<String, f64>,
+        }
+
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Fault {
+            code: i32,
+            reason: String,
+        }
+
+        /// Keyed by sensor id, which only binary formats can keep as an integer.
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Sensors {
+            by_id: BTreeMap<u32, String>,
+            total: i64,
+        }
+
+        fn ok() -> JsonResult<Telemetry, Fault> {
+            JsonResult(Ok(Telemetry {
+                device: "edge-7".to_string(),
+                sequence: 42,
+                readings: BTreeMap::from([("temp".to_string(), 21.5)]),
+            }))
+        }
+
+        fn err() -> JsonResult<Telemetry, Fault> {
+            JsonResult(Err(Fault {
+                code: -3,
+                reason: "sensor offline".to_string(),
+            }))
+        }
+
+        fn sensors() -> JsonResult<Sensors, Fault> {
+            JsonResult(Ok(Sensors {
+                by_id: BTreeMap::from([(1, "temp".to_string()), (2, "humidity".to_string())]),
+                total: -2,
+            }))
+        }
+
+        #[cfg(feature = "cbor")]
+        #[test]
+        fn test_cbor_round_trip() {
+            fn round_trip<T, E>(jr: &JsonResult<T, E>) -> JsonResult<T, E>
+            where
+                T: Serialize + serde::de::DeserializeOwned,
+                E: Serialize + serde::de::DeserializeOwned,
+            {
+                let mut buf = Vec::new();
+                ciborium::into_writer(jr, &mut buf).unwrap();
+                ciborium::from_reader(buf.as_slice()).unwrap()
+            }
+
+            assert_eq!(round_trip(&ok()).0, ok().0);
+            assert_eq!(round_trip(&err()).0, err().0);
+            assert_eq!(round_trip(&sensors()).0, sensors().0);
+
+            let big = JsonResult::<u128, String>(Ok(u128::from(u64::MAX) + 1));
+            assert_eq!(round_trip(&big).0, big.0);
+        }
+
+        #[cfg(feature = "msgpack")]
+        #[test]
+        fn test_msgpack_round_trip() {
+            fn round_trip<T, E>(jr: &JsonResult<T, E>) -> JsonResult<T, E>
+            where
+                T: Serialize + serde::de::DeserializeOwned,
+                E: Serialize + serde::de::DeserializeOwned,
+            {
+                let named = rmp_serde::to_vec_named(jr).unwrap();
+                let compact = rmp_serde::to_vec(jr).unwrap();
+
+                let from_compact: JsonResult<T, E> = rmp_serde::from_slice(&compact).unwrap();
+                assert_eq!(
+                    rmp_serde::to_vec(&from_compact).unwrap(),
+                    compact,
+                    "struct-as-array encoding"
+                );
+                rmp_serde::from_slice(&named).unwrap()
+            }
+
+            assert_eq!(round_trip(&ok()).0, ok().0);
+            assert_eq!(round_trip(&err()).0, err().0);
+            assert_eq!(round_trip(&sensors()).0, sensors().0);
+        }
+
+        #[cfg(feature = "yaml")]
+        #[test]
+        fn test_yaml_round_trip() {
+            let text = serde_yaml::to_string(&ok()).unwrap();
+            let parsed: JsonResult<Telemetry, Fault> = serde_yaml::from_str(&text).unwrap();
+            assert_eq!(parsed.0, ok().0);
+
+            let text = serde_yaml::to_string(&err()).unwrap();
+            let parsed: JsonResult<Telemetry, Fault> = serde_yaml::from_str(&text).unwrap();
+            assert_eq!(parsed.0, err().0);
+
+            let parsed: JsonResult<Telemetry, Fault> =
+                serde_yaml::from_str("code: 7\nreason: busy\n").unwrap();
+            assert_eq!(parsed.0.unwrap_err().code, 7);
+        }
+
+        #[cfg(feature = "toml")]
+        #[test]
+        fn test_toml_round_trip() {
+            let text = toml::to_string(&ok()).unwrap();
+            let parsed: JsonResult<Telemetry, Fault> = toml::from_str(&text).unwrap();
+            assert_eq!(parsed.0, ok().0);
+
+            let text = toml::to_string(&err()).unwrap();
+            let parsed: JsonResult<Telemetry, Fault> = toml::from_str(&text).unwrap();
+            assert_eq!(parsed.0, err().0);
+        }
+
+        #[cfg(feature = "yaml")]
+        #[test]
+        fn test_no_match_reports_both_branches() {
+            let err = serde_yaml::from_str::<JsonResult<Telemetry, Fault>>("code: x\n")
+                .unwrap_err()
+                .to_string();
+
+            assert!(err.contains("Telemetry"));
+            assert!(err.contains("Fault"));
+        }
+    }
 }
@@ -0,0 +1,853 @@
+//! A compact buffer for replaying one input into several types.
+//!
+//! Untagged decoding has to try `T` and then `E` against the same input, which a streaming
+//! deserializer cannot rewind. Instead of materializing a [`Value`] tree, where every object
+//! becomes a map and every string an allocation, the input is recorded once as a flat tape of
+//! tokens. Strings the input lends out are kept borrowed, and compound values record where
+//! they end so that skipping them is a jump.
+//!
+//! A [`Value`] is only built from the tape on the failure path, for error reporting.
+
+use alloc::borrow::Cow;
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::fmt;
+use core::marker::PhantomData;
+
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use serde_json::{Map, Number, Value};
+
+/// One recorded input value, in depth-first order.
+#[derive(Debug, Clone)]
+pub(crate) enum Token<'de> {
+    Null,
+    Bool(bool),
+    U64(u64),
+    I64(i64),
+    /// Only for integers outside the 64-bit range, e.g. from CBOR.
+    I128(i128),
+    U128(u128),
+    F64(f64),
+    Str(Cow<'de, str>),
+    Bytes(Cow<'de, [u8]>),
+    /// An explicit `Some`, followed by its value.
+    Some,
+    /// `len` elements follow; the next value starts at `end`.
+    Seq {
+        len: usize,
+        end: usize,
+    },
+    /// `len` key/value pairs follow; the next value starts at `end`.
+    Map {
+        len: usize,
+        end: usize,
+    },
+}
+
+/// A recorded input value.
+#[derive(Debug, Clone)]
+pub(crate) struct Tape<'de> {
+    tokens: Vec<Token<'de>>,
+    /// Whether the recorded format was human-readable, so that types which encode
+    /// differently in binary formats replay the way they were written.
+    human_readable: bool,
+}
+
+impl<'de> Tape<'de> {
+    /// A deserializer that replays the tape from the start.
+    pub(crate) fn replay(&self) -> Replay<'_, 'de> {
+        self.replay_as()
+    }
+
+    /// Like [`Tape::replay`], failing with `Er` instead of a `serde_json::Error`, so that a
+    /// payload buffered from another deserializer fails with that deserializer's own errors.
+    pub(crate) fn replay_as<Er: de::Error>(&self) -> Replay<'_, 'de, Er> {
+        Replay {
+            tape: self,
+            pos: 0,
+            watch: None,
+            omit: None,
+            error: PhantomData,
+        }
+    }
+
+    /// Like [`Tape::replay_as`] for a recorded map, leaving out the entry whose key is at `key`.
+    pub(crate) fn replay_without<Er: de::Error>(&self, key: usize) -> Replay<'_, 'de, Er> {
+        Replay {
+            omit: Some(key),
+            ..self.replay_as()
+        }
+    }
+
+    /// Like [`Tape::replay_as`], but reporting to `watch`. Only used to diagnose failures.
+    pub(crate) fn watched<'a, Er: de::Error>(&'a self, watch: &'a Watch) -> Replay<'a, 'de, Er> {
+        Replay {
+            watch: Some(watch),
+            ..self.replay_as()
+        }
+    }
+
+    pub(crate) fn tokens(&self) -> &[Token<'de>] {
+        &self.tokens
+    }
+
+    /// Builds the equivalent [`Value`], for the failure path.
+    pub(crate) fn to_value(&self) -> Value {
+        self.value_at(0).0
+    }
+
+    /// Builds the [`Value`] recorded at `pos`, for error messages.
+    pub(crate) fn to_value_at(&self, pos: usize) -> Value {
+        self.value_at(pos).0
+    }
+
+    fn value_at(&self, pos: usize) -> (Value, usize) {
+        match &self.tokens[pos] {
+            Token::Null => (Value::Null, pos + 1),
+            Token::Bool(b) => (Value::Bool(*b), pos + 1),
+            Token::U64(u) => (Value::from(*u), pos + 1),
+            Token::I64(i) => (Value::from(*i), pos + 1),
+            Token::I128(i) => (
+                Number::from_i128(*i).map_or(Value::Null, Value::Number),
+                pos + 1,
+            ),
+            Token::U128(u) => (
+                Number::from_u128(*u).map_or(Value::Null, Value::Number),
+                pos + 1,
+            ),
+            Token::F64(f) => (
+                Number::from_f64(*f).map_or(Value::Null, Value::Number),
+                pos + 1,
+            ),
+            Token::Str(s) => (Value::String(s.to_string()), pos + 1),
+            Token::Bytes(b) => (
+                Value::Array(b.iter().map(|byte| Value::from(*byte)).collect()),
+                pos + 1,
+            ),
+            Token::Some => self.value_at(pos + 1),
+            Token::Seq { len, end } => {
+                let mut items = Vec::with_capacity(*len);
+                let mut next = pos + 1;
+                for _ in 0..*len {
+                    let (item, after) = self.value_at(next);
+                    items.push(item);
+                    next = after;
+                }
+                (Value::Array(items), *end)
+            }
+            Token::Map { len, end } => {
+                let mut map = Map::new();
+                let mut next = pos + 1;
+                for _ in 0..*len {
+                    let (key, after) = self.value_at(next);
+                    let (value, after) = self.value_at(after);
+                    let key = match key {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    map.insert(key, value);
+                    next = after;
+                }
+                (Value::Object(map), *end)
+            }
+        }
+    }
+}
+
+/// Records a borrowed [`Value`], lending out its strings.
+impl<'de> From<&'de Value> for Tape<'de> {
+    fn from(value: &'de Value) -> Self {
+        fn push<'de>(tokens: &mut Vec<Token<'de>>, value: &'de Value) {
+            match value {
+                Value::Null => tokens.push(Token::Null),
+                Value::Bool(b) => tokens.push(Token::Bool(*b)),
+                Value::Number(n) => tokens.push(match (n.as_u64(), n.as_i64()) {
+                    (Some(u), _) => Token::U64(u),
+                    (_, Some(i)) => Token::I64(i),
+                    _ => Token::F64(n.as_f64().unwrap_or(f64::NAN)),
+                }),
+                Value::String(s) => tokens.push(Token::Str(Cow::Borrowed(s))),
+                Value::Array(items) => {
+                    let start = tokens.len();
+                    tokens.push(Token::Seq { len: 0, end: 0 });
+                    for item in items {
+                        push(tokens, item);
+                    }
+                    let end = tokens.len();
+                    tokens[start] = Token::Seq {
+                        len: items.len(),
+                        end,
+                    };
+                }
+                Value::Object(map) => {
+                    let start = tokens.len();
+                    tokens.push(Token::Map { len: 0, end: 0 });
+                    for (key, value) in map {
+                        tokens.push(Token::Str(Cow::Borrowed(key)));
+                        push(tokens, value);
+                    }
+                    let end = tokens.len();
+                    tokens[start] = Token::Map {
+                        len: map.len(),
+                        end,
+                    };
+                }
+            }
+        }
+
+        let mut tokens = Vec::new();
+        push(&mut tokens, value);
+        Tape {
+            tokens,
+            human_readable: true,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Tape<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let human_readable = deserializer.is_human_readable();
+        let mut tokens = Vec::new();
+        Recorder {
+            tokens: &mut tokens,
+        }
+        .deserialize(deserializer)?;
+        Ok(Tape {
+            tokens,
+            human_readable,
+        })
+    }
+}
+
+/// Appends one value to the tape.
+struct Recorder<'t, 'de> {
+    tokens: &'t mut Vec<Token<'de>>,
+}
+
+impl<'t, 'de> Recorder<'t, 'de> {
+    fn push<E>(self, token: Token<'de>) -> Result<(), E> {
+        self.tokens.push(token);
+        Ok(())
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Recorder<'_, 'de> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Recorder<'_, 'de> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<(), E> {
+        self.push(Token::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<(), E> {
+        self.push(Token::I64(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<(), E> {
+        self.push(Token::U64(v))
+    }
+
+    fn visit_i128<E>(self, v: i128) -> Result<(), E> {
+        match i64::try_from(v) {
+            Ok(v) => self.push(Token::I64(v)),
+            Err(_) => self.push(Token::I128(v)),
+        }
+    }
+
+    fn visit_u128<E>(self, v: u128) -> Result<(), E> {
+        match u64::try_from(v) {
+            Ok(v) => self.push(Token::U64(v)),
+            Err(_) => self.push(Token::U128(v)),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<(), E> {
+        self.push(Token::F64(v))
+    }
+
+    fn visit_char<E>(self, v: char) -> Result<(), E> {
+        self.push(Token::Str(Cow::Owned(v.to_string())))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<(), E> {
+        self.push(Token::Str(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<(), E> {
+        self.push(Token::Str(Cow::Borrowed(v)))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<(), E> {
+        self.push(Token::Str(Cow::Owned(v)))
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<(), E> {
+        self.push(Token::Bytes(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<(), E> {
+        self.push(Token::Bytes(Cow::Borrowed(v)))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<(), E> {
+        self.push(Token::Bytes(Cow::Owned(v)))
+    }
+
+    fn visit_none<E>(self) -> Result<(), E> {
+        self.push(Token::Null)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        self.tokens.push(Token::Some);
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_unit<E>(self) -> Result<(), E> {
+        self.push(Token::Null)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let start = self.tokens.len();
+        self.tokens.push(Token::Seq { len: 0, end: 0 });
+
+        let mut len = 0;
+        while seq
+            .next_element_seed(Recorder {
+                tokens: &mut *self.tokens,
+            })?
+            .is_some()
+        {
+            len += 1;
+        }
+
+        let end = self.tokens.len();
+        self.tokens[start] = Token::Seq { len, end };
+        Ok(())
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let start = self.tokens.len();
+        self.tokens.push(Token::Map { len: 0, end: 0 });
+
+        let mut len = 0;
+        while map
+            .next_key_seed(Recorder {
+                tokens: &mut *self.tokens,
+            })?
+            .is_some()
+        {
+            map.next_value_seed(Recorder {
+                tokens: &mut *self.tokens,
+            })?;
+            len += 1;
+        }
+
+        let end = self.tokens.len();
+        self.tokens[start] = Token::Map { len, end };
+        Ok(())
+    }
+}
+
+/// Replays one recorded value, mirroring how `serde_json` deserializes a borrowed [`Value`] so
+/// that both produce the same errors.
+pub(crate) struct Replay<'a, 'de, Er = serde_json::Error> {
+    tape: &'a Tape<'de>,
+    pos: usize,
+    watch: Option<&'a Watch>,
+    /// Position of a key whose entry the map at `pos` leaves out.
+    omit: Option<usize>,
+    error: PhantomData<fn() -> Er>,
+}
+
+/// Observes a replay for failure diagnostics: records the innermost struct the replay failed
+/// in.
+#[derive(Default)]
+pub(crate) struct Watch {
+    failed: Cell<Option<FailedStruct>>,
+}
+
+/// An object a replay was reading as a struct when it failed.
+#[derive(Clone, Copy)]
+pub(crate) struct FailedStruct {
+    /// Position of the object on the tape.
+    pub(crate) pos: usize,
+    /// The fields the struct declares.
+    pub(crate) fields: &'static [&'static str],
+}
+
+impl Watch {
+    /// The innermost struct the replay failed in, if it failed in one.
+    pub(crate) fn failed(&self) -> Option<FailedStruct> {
+        self.failed.get()
+    }
+
+    fn fail(&self, pos: usize, end: usize, fields: &'static [&'static str]) {
+        // A struct nested inside this one that already failed is where the error started.
+        if !matches!(self.failed.get(), Some(inner) if (pos..end).contains(&inner.pos)) {
+            self.failed.set(Some(FailedStruct { pos, fields }));
+        }
+    }
+}
+
+impl<'a, 'de, Er> Replay<'a, 'de, Er> {
+    fn token(&self) -> &'a Token<'de> {
+        &self.tape.tokens[self.pos]
+    }
+
+    /// Replays the value at `pos` of the same tape.
+    fn at(&self, pos: usize) -> Self {
+        Replay {
+            tape: self.tape,
+            pos,
+            watch: self.watch,
+            omit: None,
+            error: PhantomData,
+        }
+    }
+}
+
+/// Position of the value after the one at `pos`.
+pub(crate) fn skip(tokens: &[Token<'_>], pos: usize) -> usize {
+    match tokens[pos] {
+        Token::Seq { end, .. } | Token::Map { end, .. } => end,
+        Token::Some => skip(tokens, pos + 1),
+        _ => pos + 1,
+    }
+}
+
+impl Token<'_> {
+    /// How the token reads in an `invalid type` error.
+    pub(crate) fn unexpected(&self) -> Unexpected<'_> {
+        match self {
+            Token::Null => Unexpected::Unit,
+            Token::Bool(b) => Unexpected::Bool(*b),
+            Token::U64(u) => Unexpected::Unsigned(*u),
+            Token::I64(i) => Unexpected::Signed(*i),
+            Token::I128(_) => Unexpected::Other("i128"),
+            Token::U128(_) => Unexpected::Other("u128"),
+            Token::F64(f) => Unexpected::Float(*f),
+            Token::Str(s) => Unexpected::Str(s),
+            Token::Bytes(b) => Unexpected::Bytes(b),
+            Token::Some => Unexpected::Option,
+            Token::Seq { .. } => Unexpected::Seq,
+            Token::Map { .. } => Unexpected::Map,
+        }
+    }
+}
+
+impl<'de, Er: de::Error> Deserializer<'de> for Replay<'_, 'de, Er> {
+    type Error = Er;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.token() {
+            Token::Null => visitor.visit_unit(),
+            Token::Bool(b) => visitor.visit_bool(*b),
+            Token::U64(u) => visitor.visit_u64(*u),
+            Token::I64(i) => visitor.visit_i64(*i),
+            Token::I128(i) => visitor.visit_i128(*i),
+            Token::U128(u) => visitor.visit_u128(*u),
+            Token::F64(f) => visitor.visit_f64(*f),
+            Token::Str(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            Token::Str(Cow::Owned(s)) => visitor.visit_str(s),
+            Token::Bytes(Cow::Borrowed(b)) => visitor.visit_borrowed_bytes(b),
+            Token::Bytes(Cow::Owned(b)) => visitor.visit_bytes(b),
+            Token::Some => visitor.visit_some(self.at(self.pos + 1)),
+            Token::Seq { len, .. } => {
+                let mut seq = ReplaySeq {
+                    replay: self.at(self.pos + 1),
+                    remaining: *len,
+                };
+                let value = visitor.visit_seq(&mut seq)?;
+                if seq.remaining == 0 {
+                    Ok(value)
+                } else {
+                    Err(de::Error::invalid_length(*len, &"fewer elements in array"))
+                }
+            }
+            Token::Map { len, .. } => {
+                let mut map = ReplayMap {
+                    replay: self.at(self.pos + 1),
+                    omit: self.omit,
+                    remaining: *len,
+                };
+                let value = visitor.visit_map(&mut map)?;
+                if map.remaining == 0 {
+                    Ok(value)
+                } else {
+                    Err(de::Error::invalid_length(*len, &"fewer elements in map"))
+                }
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.token() {
+            Token::Null => visitor.visit_none(),
+            Token::Some => visitor.visit_some(self.at(self.pos + 1)),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.token() {
+            Token::Str(_) => visitor.visit_enum(ReplayEnum {
+                variant: self,
+                value: None,
+            }),
+            Token::Map { len: 1, .. } => {
+                let key = self.pos + 1;
+                visitor.visit_enum(ReplayEnum {
+                    variant: self.at(key),
+                    value: Some(self.at(skip(&self.tape.tokens, key))),
+                })
+            }
+            Token::Map { .. } => Err(de::Error::invalid_value(
+                Unexpected::Map,
+                &"map with a single key",
+            )),
+            _ => Err(de::Error::invalid_type(
+                self.token().unexpected(),
+                &"string or map",
+            )),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (watch, pos) = (self.watch, self.pos);
+        let end = match self.token() {
+            Token::Map { end, .. } => Some(*end),
+            _ => None,
+        };
+        let result = self.deserialize_any(visitor);
+        if let (Err(_), Some(watch), Some(end)) = (&result, watch, end) {
+            watch.fail(pos, end, fields);
+        }
+        result
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.tape.human_readable
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map identifier
+    }
+}
+
+/// `replay` points at the next element.
+struct ReplaySeq<'a, 'de, Er> {
+    replay: Replay<'a, 'de, Er>,
+    remaining: usize,
+}
+
+impl<'de, Er: de::Error> SeqAccess<'de> for ReplaySeq<'_, 'de, Er> {
+    type Error = Er;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let element = self.replay.at(self.replay.pos);
+        self.replay.pos = skip(&self.replay.tape.tokens, element.pos);
+        self.remaining -= 1;
+        seed.deserialize(element).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+/// `replay` points at the next key or value.
+struct ReplayMap<'a, 'de, Er> {
+    replay: Replay<'a, 'de, Er>,
+    /// Position of a key whose entry is skipped.
+    omit: Option<usize>,
+    remaining: usize,
+}
+
+impl<'de, Er: de::Error> MapAccess<'de> for ReplayMap<'_, 'de, Er> {
+    type Error = Er;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.remaining > 0 && self.omit == Some(self.replay.pos) {
+            let tokens = &self.replay.tape.tokens;
+            self.replay.pos = skip(tokens, skip(tokens, self.replay.pos));
+            self.remaining -= 1;
+            self.omit = None;
+        }
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let element = self.replay.at(self.replay.pos);
+        self.replay.pos = skip(&self.replay.tape.tokens, element.pos);
+        self.remaining -= 1;
+        seed.deserialize(element).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self.replay.at(self.replay.pos);
+        self.replay.pos = skip(&self.replay.tape.tokens, value.pos);
+        seed.deserialize(value)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining - usize::from(self.omit.is_some()))
+    }
+}
+
+struct ReplayEnum<'a, 'de, Er> {
+    variant: Replay<'a, 'de, Er>,
+    value: Option<Replay<'a, 'de, Er>>,
+}
+
+impl<'a, 'de, Er: de::Error> EnumAccess<'de> for ReplayEnum<'a, 'de, Er> {
+    type Error = Er;
+    type Variant = ReplayVariant<'a, 'de, Er>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = seed.deserialize(self.variant)?;
+        Ok((variant, ReplayVariant { value: self.value }))
+    }
+}
+
+struct ReplayVariant<'a, 'de, Er> {
+    value: Option<Replay<'a, 'de, Er>>,
+}
+
+impl<'de, Er: de::Error> VariantAccess<'de> for ReplayVariant<'_, 'de, Er> {
+    type Error = Er;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        match self.value {
+            Some(value) => Deserialize::deserialize(value),
+            None => Ok(()),
+        }
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.value {
+            Some(value) => seed.deserialize(value),
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(value) => match value.token() {
+                Token::Seq { .. } => value.deserialize_any(visitor),
+                _ => Err(de::Error::invalid_type(
+                    value.token().unexpected(),
+                    &"tuple variant",
+                )),
+            },
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            Some(value) => match value.token() {
+                Token::Map { .. } => value.deserialize_struct("", fields, visitor),
+                _ => Err(de::Error::invalid_type(
+                    value.token().unexpected(),
+                    &"struct variant",
+                )),
+            },
+            None => Err(de::Error::invalid_type(
+                Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::{Tape, Token};
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Unit,
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Everything {
+        id: u64,
+        delta: i64,
+        ratio: f64,
+        name: String,
+        flag: bool,
+        maybe: Option<u32>,
+        nothing: Option<u32>,
+        list: Vec<u8>,
+        pair: (String, u32),
+        map: BTreeMap<String, i32>,
+        shapes: Vec<Shape>,
+    }
+
+    const INPUT: &str = r#"{
+        "id": 7, "delta": -3, "ratio": 0.5, "name": "seven", "flag": true,
+        "maybe": 1, "nothing": null, "list": [1, 2, 3], "pair": ["a", 1],
+        "map": {"b": 2, "a": 1}, "shapes": ["Unit", {"Circle": 1.5}, {"Rect": {"w": 1, "h": 2}}],
+        "ignored": {"deep": [1, {"x": null}]}
+    }"#;
+
+    #[test]
+    fn test_replay_matches_direct_decode() {
+        let tape: Tape = serde_json::from_str(INPUT).unwrap();
+
+        let replayed = Everything::deserialize(tape.replay()).unwrap();
+        let direct: Everything = serde_json::from_str(INPUT).unwrap();
+        assert_eq!(replayed, direct);
+
+        // A tape can be replayed any number of times.
+        assert_eq!(Everything::deserialize(tape.replay()).unwrap(), direct);
+    }
+
+    #[test]
+    fn test_strings_borrow_from_input() {
+        let tape: Tape = serde_json::from_str(r#"{"plain": "abc", "escaped": "a\nb"}"#).unwrap();
+
+        let strings: Vec<_> = tape
+            .tokens
+            .iter()
+            .filter_map(|t| match t {
+                Token::Str(s) => Some(s),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(strings[1], Cow::Borrowed("abc")));
+        assert!(matches!(strings[3], Cow::Owned(s) if s == "a\nb"));
+    }
+
+    #[test]
+    fn test_to_value_round_trips() {
+        let tape: Tape = serde_json::from_str(INPUT).unwrap();
+        let value: serde_json::Value = serde_json::from_str(INPUT).unwrap();
+
+        assert_eq!(tape.to_value(), value);
+    }
+
+    #[test]
+    fn test_errors_match_value_decode() {
+        let cases = [
+            json!({ "id": "x" }),
+            json!([1, 2]),
+            json!({ "shapes": [{ "Square": 1 }] }),
+            json!({ "pair": ["a", 1, 2] }),
+            json!("text"),
+            json!(null),
+        ];
+
+        for value in cases {
+            let input = value.to_string();
+            let tape: Tape = serde_json::from_str(&input).unwrap();
+
+            let replayed = Everything::deserialize(tape.replay()).unwrap_err();
+            let buffered = Everything::deserialize(&value).unwrap_err();
+            assert_eq!(replayed.to_string(), buffered.to_string(), "{}", input);
+        }
+    }
+}
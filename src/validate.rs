@@ -0,0 +1,505 @@
+//! Post-decode validation, so a `T` that parses but breaks its invariants falls through to `E`.
+//!
+//! Serde only checks shape, so an error body can "succeed" as a `T` whose fields are all
+//! optional. Wrapping the branch in [`Validated`] runs [`Validate::validate`] right after it
+//! deserializes; a value that fails validation counts as a failed parse, so `E` is tried next,
+//! and if `E` fails too the validation messages are part of the combined error.
+//!
+//! [`from_str`], [`from_slice`] and [`from_value`] validate the `Ok` type of any `JsonResult`
+//! flavour without the wrapper, and also keep the violations typed through
+//! [`BranchError::violations`](crate::error::BranchError::violations).
+//!
+//! With the `validator` feature, `validator::ValidationErrors` converts into [`Violations`], so
+//! a type deriving `validator::Validate` only needs a one-line impl:
+//! `validator::Validate::validate(self).map_err(Into::into)`.
+//!
+//! ```rust
+//! use json_result::r#struct::JsonResult;
+//! use json_result::validate::{Validate, Validated, Violations};
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct Charge {
+//!     id: Option<String>,
+//!     amount: Option<u64>,
+//! }
+//!
+//! impl Validate for Charge {
+//!     fn validate(&self) -> Result<(), Violations> {
+//!         let mut violations = Violations::new();
+//!         if self.id.is_none() {
+//!             violations.push("id", "is required");
+//!         }
+//!         violations.into_result()
+//!     }
+//! }
+//!
+//! #[derive(Debug, serde::Deserialize)]
+//! struct ApiError {
+//!     error: String,
+//! }
+//!
+//! let jr: JsonResult<Validated<Charge>, ApiError> =
+//!     serde_json::from_str(r#"{"error": "card declined"}"#).unwrap();
+//! assert_eq!(jr.0.unwrap_err().error, "card declined");
+//! ```
+
+use alloc::string::String;
+#[cfg(feature = "validator")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use serde::de::{self, DeserializeOwned};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::error::DecodeError;
+use crate::repr::ResultLike;
+use crate::repr::untagged::{self, Ambiguity};
+
+/// Invariants checked after a value deserializes, when it is wrapped in [`Validated`].
+pub trait Validate {
+    /// Returns `Err` with every broken invariant, or `Ok` when the value is acceptable.
+    fn validate(&self) -> Result<(), Violations>;
+}
+
+/// One broken invariant: the field it concerns and what is wrong with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    field: String,
+    message: String,
+}
+
+impl Violation {
+    /// The field, e.g. `amount` or `items[2].price`, or empty for the value as a whole.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// What is wrong with the field.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.field.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "`{}`: {}", self.field, self.message)
+        }
+    }
+}
+
+/// The invariants a value broke, in the order they were found.
+///
+/// Displays as `` `id`: is required; `amount`: must be positive ``.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Violations(Vec<Violation>);
+
+impl Violations {
+    /// An empty list.
+    pub fn new() -> Self {
+        Violations::default()
+    }
+
+    /// Records that `field` breaks an invariant. Use an empty `field` for the whole value.
+    pub fn push(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(Violation {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Whether nothing was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The recorded violations.
+    pub fn iter(&self) -> core::slice::Iter<'_, Violation> {
+        self.0.iter()
+    }
+
+    /// `Ok` when nothing was recorded, so [`Validate::validate`] can end with this.
+    pub fn into_result(self) -> Result<(), Violations> {
+        if self.is_empty() { Ok(()) } else { Err(self) }
+    }
+}
+
+impl fmt::Display for Violations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, violation) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            violation.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for Violations {}
+
+impl<'a> IntoIterator for &'a Violations {
+    type Item = &'a Violation;
+    type IntoIter = core::slice::Iter<'a, Violation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Collects every field error of a `validator` derive, with nested paths such as
+/// `address.city` and `items[2].price`, sorted by field.
+#[cfg(feature = "validator")]
+impl From<validator::ValidationErrors> for Violations {
+    fn from(errors: validator::ValidationErrors) -> Self {
+        fn collect(errors: &validator::ValidationErrors, prefix: &str, out: &mut Violations) {
+            use validator::ValidationErrorsKind;
+
+            let mut fields: Vec<_> = errors.errors().iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(b.0));
+            for (field, kind) in fields {
+                let path = if prefix.is_empty() {
+                    field.to_string()
+                } else {
+                    alloc::format!("{}.{}", prefix, field)
+                };
+                match kind {
+                    ValidationErrorsKind::Field(errors) => {
+                        for error in errors {
+                            let message = error.message.as_ref().unwrap_or(&error.code);
+                            out.push(path.clone(), message.as_ref());
+                        }
+                    }
+                    ValidationErrorsKind::Struct(nested) => collect(nested, &path, out),
+                    ValidationErrorsKind::List(items) => {
+                        for (index, nested) in items {
+                            collect(nested, &alloc::format!("{}[{}]", path, index), out);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut violations = Violations::new();
+        collect(&errors, "", &mut violations);
+        violations
+    }
+}
+
+/// Validated<T>
+///
+/// Deserializes `T` and then runs its [`Validate`] impl, turning broken invariants into a
+/// deserialization error that reads `validation failed: <violations>`.
+///
+/// Use it as either branch of any `JsonResult` flavour, e.g.
+/// `JsonResult<Validated<Charge>, ApiError>`. It deserializes like a newtype struct around `T`
+/// and serializes exactly like `T`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Validated<T>(pub T);
+
+impl<T> Validated<T> {
+    /// Returns the validated value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Validated<T>
+where
+    T: Deserialize<'de> + Validate,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = T::deserialize(deserializer)?;
+        match value.validate() {
+            Ok(()) => Ok(Validated(value)),
+            Err(violations) => Err(de::Error::custom(Failed(&violations))),
+        }
+    }
+}
+
+/// Displays as the error of a value that failed validation.
+pub(crate) struct Failed<'a>(pub(crate) &'a Violations);
+
+impl fmt::Display for Failed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "validation failed: {}", self.0)
+    }
+}
+
+/// Parses `s` and decodes it as `J::Ok`, checked with [`Validate`], falling back to `J::Err`.
+///
+/// A value that fails validation counts as a failed parse, as with [`Validated`]. When `J::Err`
+/// fails too, the [`DecodeError::NoMatch`] keeps the violations typed.
+pub fn from_str<'de, J>(s: &'de str) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de> + Validate,
+    J::Err: Deserialize<'de>,
+{
+    from_slice(s.as_bytes())
+}
+
+/// Like [`from_str`], for bytes.
+pub fn from_slice<'de, J>(v: &'de [u8]) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: Deserialize<'de> + Validate,
+    J::Err: Deserialize<'de>,
+{
+    let input = untagged::record(v).map_err(DecodeError::Json)?;
+    untagged::decode_validated(input, Ambiguity::PreferOk).map(J::from_result)
+}
+
+/// Like [`from_str`], for an already parsed [`Value`].
+pub fn from_value<J>(value: Value) -> Result<J, DecodeError>
+where
+    J: ResultLike,
+    J::Ok: DeserializeOwned + Validate,
+    J::Err: DeserializeOwned,
+{
+    untagged::decode_validated(&value, Ambiguity::PreferOk).map(J::from_result)
+}
+
+impl<T: Serialize> Serialize for Validated<T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Validated<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::{Validate, Validated, Violations};
+    use crate::error::DecodeError;
+    use crate::repr::{PreferOk, Untagged};
+    use crate::r#struct::JsonResult;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Charge {
+        id: Option<String>,
+        amount: Option<i64>,
+    }
+
+    impl Validate for Charge {
+        fn validate(&self) -> Result<(), Violations> {
+            let mut violations = Violations::new();
+            if self.id.is_none() {
+                violations.push("id", "is required");
+            }
+            if self.amount.is_some_and(|a| a <= 0) {
+                violations.push("amount", "must be positive");
+            }
+            violations.into_result()
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct ApiError {
+        error: String,
+        code: u16,
+    }
+
+    type Checked = JsonResult<Validated<Charge>, ApiError>;
+
+    #[test]
+    fn test_error_body_no_longer_passes_as_ok() {
+        let body = r#"{"error": "card declined", "code": 402}"#;
+
+        let unchecked: JsonResult<Charge, ApiError> = serde_json::from_str(body).unwrap();
+        assert!(unchecked.is_ok());
+
+        let checked: Checked = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            checked.0.unwrap_err(),
+            ApiError {
+                error: "card declined".to_string(),
+                code: 402
+            }
+        );
+    }
+
+    #[test]
+    fn test_valid_value_is_ok() {
+        let checked: Checked = serde_json::from_str(r#"{"id": "ch_1", "amount": 5}"#).unwrap();
+        let charge = checked.0.unwrap();
+
+        assert_eq!(charge.id.as_deref(), Some("ch_1"));
+        assert_eq!(charge.into_inner().amount, Some(5));
+    }
+
+    #[test]
+    fn test_violations_appear_in_combined_error() {
+        let err =
+            Untagged::<PreferOk>::from_str::<Validated<Charge>, ApiError>(r#"{"amount": -3}"#)
+                .unwrap_err();
+        let DecodeError::NoMatch(err) = err else {
+            panic!("expected NoMatch");
+        };
+
+        assert_eq!(
+            err.ok().error().to_string(),
+            "validation failed: `id`: is required; `amount`: must be positive"
+        );
+        assert!(err.to_string().contains("must be positive"));
+        assert!(err.err().to_string().contains("missing field `error`"));
+    }
+
+    #[test]
+    fn test_typed_violations() {
+        let err = super::from_str::<JsonResult<Charge, ApiError>>(r#"{"amount": -3}"#).unwrap_err();
+        let DecodeError::NoMatch(err) = err else {
+            panic!("expected NoMatch");
+        };
+
+        let violations = err.ok().violations().unwrap();
+        let fields: Vec<_> = violations.iter().map(|v| v.field()).collect();
+        assert_eq!(fields, ["id", "amount"]);
+        assert_eq!(
+            violations.iter().nth(1).unwrap().message(),
+            "must be positive"
+        );
+        assert_eq!(
+            err.ok().error().to_string(),
+            "validation failed: `id`: is required; `amount`: must be positive"
+        );
+        assert!(err.err().violations().is_none());
+        assert_eq!(err.violations(), Some(violations));
+    }
+
+    #[test]
+    fn test_decode_functions_fall_through_to_err() {
+        let body = r#"{"error": "card declined", "code": 402}"#;
+
+        let jr: JsonResult<Charge, ApiError> = super::from_str(body).unwrap();
+        assert_eq!(jr.0.unwrap_err().code, 402);
+
+        let jr: Result<Charge, ApiError> =
+            super::from_value(serde_json::from_str(body).unwrap()).unwrap();
+        assert_eq!(jr.unwrap_err().error, "card declined");
+
+        let jr: JsonResult<Charge, ApiError> =
+            super::from_slice(br#"{"id": "ch_1", "amount": 5}"#).unwrap();
+        assert_eq!(jr.0.unwrap().amount, Some(5));
+    }
+
+    #[test]
+    fn test_nested_validated_fails_at_its_field() {
+        #[derive(Debug, Serialize, Deserialize)]
+        struct Order {
+            charge: Validated<Charge>,
+        }
+
+        let body = serde_json::json!({ "charge": { "amount": 5 } });
+        let err = Untagged::<PreferOk>::from_value::<Order, ApiError>(body).unwrap_err();
+        let DecodeError::NoMatch(err) = err else {
+            panic!("expected NoMatch");
+        };
+
+        assert_eq!(err.ok().path(), ".charge");
+        assert_eq!(
+            err.ok().error().to_string(),
+            "validation failed: `id`: is required"
+        );
+        assert!(err.violations().is_none());
+    }
+
+    #[test]
+    fn test_serializes_like_inner() {
+        let jr: Checked = JsonResult(Ok(Validated(Charge {
+            id: Some("ch_2".to_string()),
+            amount: None,
+        })));
+
+        assert_eq!(
+            serde_json::to_string(&jr).unwrap(),
+            r#"{"id":"ch_2","amount":null}"#
+        );
+    }
+
+    #[test]
+    fn test_whole_value_violation_display() {
+        let mut violations = Violations::new();
+        violations.push("", "start must precede end");
+        violations.push("end", "is in the past");
+
+        assert_eq!(
+            violations.to_string(),
+            "start must precede end; `end`: is in the past"
+        );
+        assert_eq!(violations.iter().nth(1).unwrap().field(), "end");
+    }
+
+    #[cfg(feature = "validator")]
+    mod signup {
+        use serde::Deserialize;
+        use validator::Validate;
+
+        #[derive(Debug, Deserialize, Validate)]
+        pub struct Signup {
+            #[validate(email)]
+            pub email: Option<String>,
+            #[validate(nested)]
+            pub address: Address,
+        }
+
+        #[derive(Debug, Deserialize, Validate)]
+        pub struct Address {
+            #[validate(length(min = 1, message = "must not be empty"))]
+            pub city: String,
+        }
+
+        impl super::Validate for Signup {
+            fn validate(&self) -> Result<(), super::Violations> {
+                Validate::validate(self).map_err(Into::into)
+            }
+        }
+    }
+
+    #[cfg(feature = "validator")]
+    #[test]
+    fn test_validator_derive() {
+        use signup::Signup;
+
+        let err = Untagged::<PreferOk>::from_str::<Validated<Signup>, ApiError>(
+            r#"{"email": "nope", "address": {"city": ""}}"#,
+        )
+        .unwrap_err();
+        let DecodeError::NoMatch(err) = err else {
+            panic!("expected NoMatch");
+        };
+        assert_eq!(
+            err.ok().error().to_string(),
+            "validation failed: `address.city`: must not be empty; `email`: email"
+        );
+
+        let jr: JsonResult<Validated<Signup>, ApiError> =
+            serde_json::from_str(r#"{"email": "a@b.co", "address": {"city": "Oslo"}}"#).unwrap();
+        assert_eq!(jr.0.unwrap().address.city, "Oslo");
+    }
+}